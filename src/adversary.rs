@@ -0,0 +1,94 @@
+//! An adversarial codemaker that never commits to a secret.
+//!
+//! Rather than choosing a code up front, the evil codemaker keeps
+//! the set of all codes consistent with its answers so far. For
+//! each guess, it gives the response that keeps the largest part of
+//! that set alive, so the codebreaker always faces the worst case.
+//! It can only be pinned down by a guess that leaves one code.
+//!
+//! Knuth's algorithm still wins in five:
+//!
+//! ```rust
+//! use self::mastermind::adversary::evil;
+//! use self::mastermind::solver::Solver;
+//!
+//! let breaker = Solver::new(evil());
+//! assert!(breaker.count() <= 5);
+//! ```
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+
+use gameplay::{KeyPegs, Pattern, Rules, Shield};
+use solver::PatternSet;
+
+
+pub struct Evil {
+    s: RefCell<PatternSet>,
+}
+
+impl Default for Evil {
+    fn default() -> Self {
+        Evil { s: RefCell::new(PatternSet::all()) }
+    }
+}
+
+impl Evil {
+    pub fn new() -> Evil {
+        Evil::default()
+    }
+
+    /// Keep alive any of the codes the rules allow.
+    pub fn with_rules(rules: Rules) -> Evil {
+        Evil { s: RefCell::new(PatternSet::codes(rules)) }
+    }
+
+    /// Give the response that leaves the most codes consistent,
+    /// preferring fewer black pegs, then fewer white, among equals.
+    pub fn respond(&self, guess: &Pattern) -> KeyPegs {
+        let mut s = self.s.borrow_mut();
+        let response = {
+            let hit_count = s.hit_counts(*guess);
+            let mut responses: Vec<(&KeyPegs, &usize)> = hit_count.iter().collect();
+            responses.sort_by_key(|&(response, &count)| (Reverse(count), *response));
+            *responses[0].0
+        };
+
+        let rules = s.rules();
+        s.filter_with(&|p: &Pattern| rules.score(*p, *guess) == response);
+        response
+    }
+
+    /// The codes consistent with every response so far.
+    pub fn consistent(&self) -> PatternSet {
+        self.s.borrow().clone()
+    }
+
+    /// Commit, at last, to the least consistent code.
+    pub fn reveal(&self) -> Pattern {
+        self.s.borrow().iter().next().expect("every response is consistent with some code")
+    }
+}
+
+/// Hide an evil codemaker behind a shield.
+pub fn evil() -> Shield {
+    let maker = Evil::new();
+    Box::new(move |guess: &Pattern| maker.respond(guess))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{KeyPegs, Pattern};
+    use solver::Solver;
+    use super::Evil;
+
+    #[test]
+    fn largest_partition() {
+        let maker = Evil::new();
+        // Knuth: the worst case response to 1122 leaves 256 codes.
+        assert_eq!(maker.respond(&Solver::initial_guess()), KeyPegs::new());
+        assert_eq!(maker.consistent().len(), 256);
+        assert_eq!(maker.reveal(), Pattern::from_digits(['3', '3', '3', '3']));
+    }
+}
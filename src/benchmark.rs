@@ -0,0 +1,175 @@
+//! How many guesses does the solver take, over every possible code?
+//!
+//! Knuth's algorithm is deterministic, so rather than play each game
+//! separately, we walk its decision tree: the codes that give the
+//! same response to a guess share the next guess.
+//!
+//! Known results: for the usual four pegs and six colors, Knuth's
+//! algorithm takes 5801 guesses over all 1296 codes, an average of
+//! 4.476, and never more than five. For Bulls and Cows, minimax takes
+//! 27139 guesses over all 5040 codes, an average of 5.385, and never
+//! more than seven; the best possible average is 5.213. With black
+//! pegs only, it takes 9100 guesses, an average of 7.022, and as many
+//! as ten.
+//!
+//! In the permutation game, with black pegs only, minimax averages
+//! 5.807 guesses for n = 6, and never takes more than seven. Beyond
+//! that, guessing the least consistent code is the practical
+//! strategy: it averages 7.077 guesses for n = 7, and 8.360 for n = 8,
+//! taking as many as 14.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::Rules;
+//! use self::mastermind::benchmark::benchmark;
+//!
+//! let stats = benchmark(Rules::new(3, 3));
+//! assert_eq!(stats.games(), 27);
+//! assert!(stats.worst() <= 4);
+//! ```
+
+use std::collections::BTreeMap;
+
+use gameplay::{Pattern, Rules};
+use solver::{PatternSet, Solver, Strategy, partition};
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Stats {
+    /// How many games took each number of guesses.
+    pub histogram: BTreeMap<usize, usize>,
+}
+
+impl Stats {
+    pub fn games(&self) -> usize {
+        self.histogram.values().sum()
+    }
+
+    pub fn total_guesses(&self) -> usize {
+        self.histogram.iter().map(|(guesses, games)| guesses * games).sum()
+    }
+
+    pub fn worst(&self) -> usize {
+        *self.histogram.keys().next_back().unwrap_or(&0)
+    }
+
+    pub fn average(&self) -> f64 {
+        self.total_guesses() as f64 / self.games() as f64
+    }
+}
+
+
+/// Play Knuth's algorithm, by the given rules, against every code.
+pub fn benchmark(rules: Rules) -> Stats {
+    benchmark_with(rules, Strategy::Minimax)
+}
+
+/// Play the given strategy against every code.
+pub fn benchmark_with(rules: Rules, strategy: Strategy) -> Stats {
+    let mut stats = Stats { histogram: BTreeMap::new() };
+    explore(rules, strategy, &mut vec![], PatternSet::codes(rules), &mut stats);
+    stats
+}
+
+/// Given the guesses so far and the codes consistent with them,
+/// guess, and explore each response in turn.
+fn explore(rules: Rules, strategy: Strategy, guessed: &mut Vec<Pattern>, s: PatternSet, stats: &mut Stats) {
+    let mut breaker = Solver::observing(s.clone()).strategy(strategy);
+    breaker.guessed = guessed.clone();
+    let guess = breaker.next_guess();
+    guessed.push(guess);
+
+    for (response, part) in partition(&rules, guess, &s) {
+        if rules.is_win(&response) {
+            *stats.histogram.entry(guessed.len()).or_insert(0) += 1;
+        } else {
+            explore(rules, strategy, guessed, part, stats);
+        }
+    }
+    guessed.pop();
+}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{Rules, shield, shield_with};
+    use solver::{Solver, Strategy};
+    use super::{benchmark, benchmark_with};
+
+    #[test]
+    fn agrees_with_play() {
+        let rules = Rules::new(3, 4).no_duplicates(false);
+        let stats = benchmark(rules);
+        assert_eq!(stats.games(), 24);
+
+        let played: usize = rules.codes().map(|code| Solver::with_rules(rules, shield(code)).count()).sum();
+        assert_eq!(stats.total_guesses(), played);
+    }
+
+    #[test]
+    fn black_pegs_only() {
+        let rules = Rules::new(3, 4).black_pegs_only();
+        let stats = benchmark(rules);
+        assert_eq!(stats.games(), 64);
+        assert!(stats.worst() > benchmark(Rules::new(3, 4)).worst());
+
+        let played: usize = rules.codes().map(|code| Solver::with_rules(rules, shield_with(rules, code)).count()).sum();
+        assert_eq!(stats.total_guesses(), played);
+    }
+
+    #[test]
+    fn permutations() {
+        let rules = Rules::permutations(4);
+        assert_eq!(rules.codes().count(), 24);
+        let minimax = benchmark(rules);
+        let consistent = benchmark_with(rules, Strategy::Consistent);
+        assert_eq!((minimax.total_guesses(), minimax.worst()), (84, 4));
+        assert_eq!((consistent.total_guesses(), consistent.worst()), (88, 6));
+
+        let played: usize = rules.codes()
+            .map(|code| Solver::with_rules(rules, shield_with(rules, code)).strategy(Strategy::Consistent).count())
+            .sum();
+        assert_eq!(consistent.total_guesses(), played);
+    }
+
+    #[test]
+    #[ignore]
+    fn knuth() {
+        let stats = benchmark(Rules::default());
+        assert_eq!((stats.games(), stats.total_guesses(), stats.worst()), (1296, 5801, 5));
+    }
+
+    #[test]
+    #[ignore]
+    fn bulls_and_cows() {
+        let stats = benchmark(Rules::bulls_and_cows());
+        assert_eq!((stats.games(), stats.total_guesses(), stats.worst()), (5040, 27139, 7));
+    }
+
+    #[test]
+    #[ignore]
+    fn knuth_black_pegs_only() {
+        let stats = benchmark(Rules::default().black_pegs_only());
+        assert_eq!((stats.games(), stats.total_guesses(), stats.worst()), (1296, 9100, 10));
+    }
+
+    #[test]
+    #[ignore]
+    fn permutations_of_6() {
+        let stats = benchmark(Rules::permutations(6));
+        assert_eq!((stats.games(), stats.total_guesses(), stats.worst()), (720, 4181, 7));
+    }
+
+    #[test]
+    #[ignore]
+    fn permutations_of_7() {
+        let stats = benchmark_with(Rules::permutations(7), Strategy::Consistent);
+        assert_eq!((stats.games(), stats.total_guesses()), (5040, 35668));
+    }
+
+    #[test]
+    #[ignore]
+    fn permutations_of_8() {
+        let stats = benchmark_with(Rules::permutations(8), Strategy::Consistent);
+        assert_eq!((stats.games(), stats.total_guesses(), stats.worst()), (40320, 337080, 14));
+    }
+}
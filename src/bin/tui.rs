@@ -0,0 +1,338 @@
+//! The decoding board, full screen: the shield, a row of code peg
+//! holes and key peg holes for each turn.
+//!
+//! The human is the codebreaker: left and right pick a hole, up and
+//! down its color (or type the color's symbol), Enter guesses, ?
+//! shows how many codes remain possible, q quits. In demo mode, the
+//! solver plays, a turn at a time.
+//!
+//! Built with the `tui` feature: `cargo run --features tui --bin mmind-tui`.
+
+extern crate mastermind;
+extern crate rand;
+extern crate termion;
+
+use std::env;
+use std::io;
+use std::io::Write;
+use std::process;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use rand::Rng;
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use termion::{clear, cursor};
+
+use mastermind::gameplay::{DecodingBoard, KeyPegs, Pattern, Rules, seeded_rng, shield_with};
+use mastermind::render::{Palette, Renderer};
+use mastermind::solver::{PatternSet, Solver};
+
+
+const USAGE: &str = "usage: mmind-tui [OPTIONS] [demo]
+
+options:
+  --seed N              reproduce a game
+  --pegs N              pegs per pattern (4)
+  --colors N            colors of code pegs (6)
+  --no-color            write colors as digits, not colored circles
+  --color-blind         colors for color blindness, labeled with their symbols";
+
+/// Between the solver's turns in demo mode.
+const PAUSE: Duration = Duration::from_millis(700);
+
+pub fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    let mut seed = None;
+    let (mut pegs, mut colors) = (Pattern::size() as u8, 6);
+    let mut palette = Some(Palette::Classic);
+    while let Some(opt) = args.first().cloned().filter(|a| a.starts_with("--")) {
+        let mut value = || if args.len() > 1 { args.remove(1) } else { usage() };
+        match opt {
+            "--seed" => seed = Some(number(value())),
+            "--pegs" => pegs = number(value()),
+            "--colors" => colors = number(value()),
+            "--no-color" => palette = None,
+            "--color-blind" => palette = palette.map(|_| Palette::ColorBlind),
+            _ => usage(),
+        }
+        args.remove(0);
+    }
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let rules = Rules::checked(pegs, colors).unwrap_or_else(|| usage());
+    let renderer = palette.map(Renderer::ansi).unwrap_or_else(Renderer::plain);
+    let board = Board::new(rules, seed, renderer);
+
+    let done = match args.as_slice() {
+        [] => play(board),
+        ["demo"] => demo(board),
+        _ => usage(),
+    };
+    if let Err(e) = done {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+/// An option's value, or usage if it doesn't parse or doesn't fit.
+fn number<T: FromStr>(value: &str) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+
+/// The state of a game, as the board shows it.
+struct Board {
+    rules: Rules,
+    rows: usize,
+    seed: u64,
+    secret: Pattern,
+    turns: Vec<(Pattern, KeyPegs)>,
+    /// The codes consistent with the turns so far.
+    possible: PatternSet,
+    show_possible: bool,
+    /// The row being set, and the hole being set in it.
+    holes: Vec<Option<u8>>,
+    cursor: usize,
+    message: String,
+    renderer: Renderer,
+}
+
+impl Board {
+    fn new(rules: Rules, seed: u64, renderer: Renderer) -> Board {
+        Board {
+            rules,
+            rows: DecodingBoard::default().rows as usize,
+            seed,
+            secret: rules.random(&mut seeded_rng(seed)),
+            turns: vec![],
+            possible: PatternSet::codes(rules),
+            show_possible: false,
+            holes: vec![None; rules.pegs as usize],
+            cursor: 0,
+            message: String::new(),
+            renderer,
+        }
+    }
+
+    fn record(&mut self, guess: Pattern) -> KeyPegs {
+        let (rules, response) = (self.rules, self.rules.score(self.secret, guess));
+        self.possible.filter_with(&|code| rules.score(*code, guess) == response);
+        self.turns.push((guess, response));
+        response
+    }
+
+    fn is_over(&self) -> bool {
+        self.turns.last().is_some_and(|&(_, response)| self.rules.is_win(&response)) || self.turns.len() >= self.rows
+    }
+
+    /// Change the color of the hole at the cursor, cycling through
+    /// the colors.
+    fn turn_color(&mut self, up: bool) {
+        let n = self.rules.symbols();
+        let hole = &mut self.holes[self.cursor];
+        *hole = Some(match (*hole, up) {
+            (None, true) => 0,
+            (None, false) => n - 1,
+            (Some(c), true) => (c + 1) % n,
+            (Some(c), false) => (c + n - 1) % n,
+        });
+    }
+
+    /// Guess the row being set, if every hole is filled.
+    fn guess(&mut self) {
+        let holes: Option<Vec<u8>> = self.holes.iter().cloned().collect();
+        let guess = match holes {
+            Some(holes) => self.rules.pattern(&holes),
+            None => {
+                self.message = "fill every hole first".to_string();
+                return;
+            }
+        };
+        if !self.rules.is_guess(&guess) {
+            self.message = "not allowed as a guess".to_string();
+            return;
+        }
+        self.record(guess);
+        self.holes = vec![None; self.rules.pegs as usize];
+        self.cursor = 0;
+        self.message = match self.turns.last() {
+            Some(&(_, response)) if self.rules.is_win(&response) => {
+                format!("codebreaker wins in {}; any key to quit", self.turns.len())
+            }
+            _ if self.is_over() => "codemaker wins; any key to quit".to_string(),
+            _ => String::new(),
+        };
+    }
+
+    fn hole(&self, color: Option<u8>) -> String {
+        match color {
+            Some(c) => self.renderer.code_peg(self.rules, c),
+            None => "·".to_string(),
+        }
+    }
+
+    fn key_holes(&self, response: Option<KeyPegs>) -> String {
+        let (keys, used) = match response {
+            Some(k) => (self.renderer.key_pegs(k), (k.black_count() + k.white_count()) as usize),
+            None => (String::new(), 0),
+        };
+        keys + &"·".repeat(self.rules.pegs as usize - used)
+    }
+
+    /// The lines of the board, top row first, as on the table, with
+    /// the shield at the top.
+    fn lines(&self, editing: bool) -> Vec<String> {
+        let width = (0..self.rules.symbols())
+            .map(|c| visible_width(&self.renderer.code_peg(self.rules, c)))
+            .max()
+            .unwrap_or(1);
+        let pad = |s: String| {
+            let n = visible_width(&s);
+            s + &" ".repeat(width.saturating_sub(n))
+        };
+        let row = |holes: Vec<String>, cursor: Option<usize>| -> String {
+            holes.into_iter()
+                .enumerate()
+                .map(|(pos, s)| if cursor == Some(pos) { format!("[{}]", pad(s)) } else { format!(" {} ", pad(s)) })
+                .collect()
+        };
+
+        let mut lines = vec![format!("mmind  seed {}", self.seed), String::new()];
+        let shield = if self.is_over() {
+            self.secret.code_pegs().into_iter().map(|c| self.hole(Some(c))).collect()
+        } else {
+            vec!["■".to_string(); self.rules.pegs as usize]
+        };
+        lines.push(format!("     {}", row(shield, None)));
+        lines.push(String::new());
+
+        for r in (0..self.rows).rev() {
+            let line = match self.turns.get(r) {
+                Some(&(guess, response)) => {
+                    let holes = guess.code_pegs().into_iter().map(|c| self.hole(Some(c))).collect();
+                    format!("{:>2} │ {} │ {}", r + 1, row(holes, None), self.key_holes(Some(response)))
+                }
+                None if editing && r == self.turns.len() && !self.is_over() => {
+                    let holes = self.holes.iter().map(|&c| self.hole(c)).collect();
+                    format!("{:>2} │ {} │ {}", r + 1, row(holes, Some(self.cursor)), self.key_holes(None))
+                }
+                None => {
+                    let holes = vec!["·".to_string(); self.rules.pegs as usize];
+                    format!("{:>2} │ {} │ {}", r + 1, row(holes, None), self.key_holes(None))
+                }
+            };
+            lines.push(line);
+        }
+
+        lines.push(String::new());
+        if self.show_possible {
+            lines.push(format!("possible codes: {}", self.possible.len()));
+        }
+        if editing {
+            lines.push("←→ hole  ↑↓ color  Enter guess  ? possible codes  q quit".to_string());
+        }
+        lines.push(self.message.clone());
+        lines
+    }
+}
+
+/// Width on screen, not counting ANSI escape sequences.
+fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut escape = false;
+    for c in s.chars() {
+        match c {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+fn draw<W: Write>(screen: &mut W, board: &Board, editing: bool) -> io::Result<()> {
+    write!(screen, "{}", clear::All)?;
+    for (ix, line) in board.lines(editing).iter().enumerate() {
+        write!(screen, "{}{}", cursor::Goto(1, ix as u16 + 1), line)?;
+    }
+    screen.flush()
+}
+
+
+/// The human is the codebreaker.
+fn play(mut board: Board) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut screen = AlternateScreen::from(io::stdout().into_raw_mode()?);
+    write!(screen, "{}", cursor::Hide)?;
+
+    let mut keys = stdin.keys();
+    loop {
+        draw(&mut screen, &board, true)?;
+        let key = match keys.next() {
+            Some(key) => key?,
+            None => break,
+        };
+        if board.is_over() {
+            break;
+        }
+        board.message.clear();
+        match key {
+            Key::Char('q') | Key::Esc | Key::Ctrl('c') => break,
+            Key::Left => board.cursor = board.cursor.saturating_sub(1),
+            Key::Right => board.cursor = (board.cursor + 1).min(board.holes.len() - 1),
+            Key::Up => board.turn_color(true),
+            Key::Down => board.turn_color(false),
+            Key::Char('\n') => board.guess(),
+            Key::Char('?') => board.show_possible = !board.show_possible,
+            Key::Char(c) => match board.rules.alphabet.color(c).filter(|&c| c < board.rules.colors) {
+                Some(color) => {
+                    board.holes[board.cursor] = Some(color);
+                    board.cursor = (board.cursor + 1).min(board.holes.len() - 1);
+                }
+                None => board.message = format!("not a color: {:?}", c),
+            },
+            _ => {}
+        }
+    }
+    write!(screen, "{}", cursor::Show)?;
+    screen.flush()
+}
+
+
+/// The solver plays, a turn at a time.
+fn demo(mut board: Board) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut screen = AlternateScreen::from(io::stdout().into_raw_mode()?);
+    write!(screen, "{}", cursor::Hide)?;
+    board.show_possible = true;
+
+    let breaker = Solver::with_rules(board.rules, shield_with(board.rules, board.secret));
+    draw(&mut screen, &board, false)?;
+    for guess in breaker.take(board.rows) {
+        thread::sleep(PAUSE);
+        board.record(guess);
+        draw(&mut screen, &board, false)?;
+    }
+    board.message = match board.turns.last() {
+        Some(&(_, response)) if board.rules.is_win(&response) => {
+            format!("codebreaker wins in {}; any key to quit", board.turns.len())
+        }
+        _ => "codemaker wins; any key to quit".to_string(),
+    };
+    draw(&mut screen, &board, false)?;
+
+    stdin.keys().next();
+    write!(screen, "{}", cursor::Show)?;
+    screen.flush()
+}
@@ -0,0 +1,154 @@
+//! Checking a history of guesses and responses for consistency.
+//!
+//! A human codemaker's feedback is error-prone. A history is
+//! consistent if some code would give every recorded response; if
+//! none does, some responses must be wrong, and we look for the
+//! fewest that could be.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::{Pattern, Rules};
+//! use self::mastermind::consistency::{is_consistent, conflict, diagnose};
+//!
+//! let rules = Rules::default();
+//! let p = |s: &str| s.parse::<Pattern>().unwrap();
+//! let k = |s: &str| s.parse().unwrap();
+//! let history = vec![(p("1122"), k("BB")),
+//!                    (p("1133"), k("BB")),
+//!                    (p("2233"), k("BB"))];
+//! assert!(is_consistent(rules, &history[..2]));
+//! assert!(!is_consistent(rules, &history));
+//!
+//! // your feedback on turn 3 contradicts turns 1 and 2
+//! assert_eq!(conflict(rules, &history, 3), vec![1, 2]);
+//!
+//! // Any one response could be the mistake.
+//! let fixes = diagnose(rules, &history);
+//! assert!(fixes.iter().all(|fix| fix.corrections.len() == 1));
+//! assert!(fixes.iter().any(|fix| fix.corrections[0].turn == 3));
+//! ```
+
+use std::collections::BTreeMap;
+
+use gameplay::{KeyPegs, Pattern, Rules};
+use record::Discrepancy;
+use solver::PatternSet;
+
+
+/// The codes the rules allow that would give every response in the
+/// history.
+pub fn consistent_codes(rules: Rules, history: &[(Pattern, KeyPegs)]) -> PatternSet {
+    let mut s = PatternSet::codes(rules);
+    for &(guess, response) in history {
+        s.filter_with(&|p: &Pattern| rules.score(*p, guess) == response);
+    }
+    s
+}
+
+pub fn is_consistent(rules: Rules, history: &[(Pattern, KeyPegs)]) -> bool {
+    !consistent_codes(rules, history).is_empty()
+}
+
+
+/// One way to restore consistency: correct these responses, and
+/// any of these codes is consistent with the history.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Correction {
+    pub corrections: Vec<Discrepancy>,
+    pub codes: PatternSet,
+}
+
+/// Find the minimal sets of responses that must be wrong, along with
+/// the corrections that would restore consistency. A consistent
+/// history needs no corrections.
+pub fn diagnose(rules: Rules, history: &[(Pattern, KeyPegs)]) -> Vec<Correction> {
+    let mut fewest = history.len() + 1;
+    let mut fixes: BTreeMap<Vec<Discrepancy>, PatternSet> = BTreeMap::new();
+
+    for code in rules.codes() {
+        let wrong: Vec<Discrepancy> = history.iter()
+            .enumerate()
+            .map(|(ix, &(guess, recorded))| {
+                Discrepancy {
+                    turn: ix + 1,
+                    recorded,
+                    actual: rules.score(code, guess),
+                }
+            })
+            .filter(|d| d.recorded != d.actual)
+            .collect();
+
+        if wrong.len() < fewest {
+            fewest = wrong.len();
+            fixes.clear();
+        }
+        if wrong.len() == fewest {
+            fixes.entry(wrong).or_insert_with(|| PatternSet::empty(rules)).insert(code);
+        }
+    }
+
+    fixes.into_iter()
+        .map(|(corrections, codes)| Correction { corrections, codes })
+        .collect()
+}
+
+
+/// Which earlier turns does the response on the given turn (counting
+/// from 1) contradict? The result is minimal: the response is
+/// consistent with any proper subset of those turns. If it's
+/// consistent with all earlier turns, there is no conflict.
+pub fn conflict(rules: Rules, history: &[(Pattern, KeyPegs)], turn: usize) -> Vec<usize> {
+    let latest = history[turn - 1];
+    let with_latest = |turns: &[usize]| {
+        let mut h: Vec<(Pattern, KeyPegs)> = turns.iter().map(|t| history[t - 1]).collect();
+        h.push(latest);
+        h
+    };
+
+    let mut culprits: Vec<usize> = (1..turn).collect();
+    if is_consistent(rules, &with_latest(&culprits)) {
+        return vec![];
+    }
+
+    // Drop each turn the contradiction doesn't depend on.
+    for t in 1..turn {
+        let without: Vec<usize> = culprits.iter().cloned().filter(|c| *c != t).collect();
+        if !is_consistent(rules, &with_latest(&without)) {
+            culprits = without;
+        }
+    }
+    culprits
+}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{KeyPegs, Pattern, Rules};
+    use super::{conflict, diagnose};
+
+    #[test]
+    fn consistent_history() {
+        let secret = Pattern::from_digits(['3', '6', '2', '5']);
+        let history: Vec<(Pattern, KeyPegs)> = ["1122", "3344", "5566"]
+            .iter()
+            .map(|g| g.parse().unwrap())
+            .map(|g| (g, secret.score(g)))
+            .collect();
+
+        let fixes = diagnose(Rules::default(), &history);
+        assert_eq!(fixes.len(), 1);
+        assert!(fixes[0].corrections.is_empty());
+        assert!(fixes[0].codes.contains(&secret));
+        assert_eq!(conflict(Rules::default(), &history, 3), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn too_many_pegs() {
+        // Five key pegs for guesses of disjoint colors can't be.
+        let history: Vec<(Pattern, KeyPegs)> = [("1122", "BB"), ("3344", "W"), ("5566", "BW")]
+            .iter()
+            .map(|&(g, r)| (g.parse().unwrap(), r.parse().unwrap()))
+            .collect();
+        assert_eq!(conflict(Rules::default(), &history, 3), vec![1, 2]);
+        assert!(diagnose(Rules::default(), &history).iter().all(|fix| fix.corrections.len() == 1));
+    }
+}
@@ -65,10 +65,21 @@
 
 // TODO: points, multiple games
 
+use std::collections::BTreeSet;
+use std::error::Error;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::Hash;
 use std::iter;
 use std::ops::Range;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use rand::{Rng, SeedableRng, StdRng};
+use rand::distributions::{IndependentSample, Range as Uniform};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
 
 pub struct DecodingBoard {
     pub rows: u8,
@@ -92,9 +103,512 @@ impl CodePeg {
 /// The codemaker chooses a pattern of four code pegs. Duplicates are
 /// allowed, so the player could even choose four code pegs of the same
 /// color.
-#[derive(PartialEq, Eq, Copy, Clone)]
+///
+/// Under other `Rules`, a pattern may have more or fewer pegs of
+/// more or fewer colors, and perhaps blanks; it is identified by its
+/// lexical index among all patterns of its shape, and written in the
+/// rules' alphabet.
+#[derive(PartialEq, Eq, Copy, Clone, Hash)]
 #[derive(PartialOrd, Ord)]
-pub struct Pattern(u32);
+pub struct Pattern {
+    ix: u32,
+    pegs: u8,
+    /// Colors, plus one if the last symbol is a blank.
+    symbols: u8,
+    blank: bool,
+    alphabet: Alphabet,
+}
+
+
+/// Symbols for up to 36 colors: 1-6 for the usual six, 0 for a tenth
+/// as in Bulls and Cows, then letters.
+const DIGITS: &[u8] = b"1234567890ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// An empty hole, where the rules allow it.
+pub const BLANK: char = '_';
+
+pub const MAX_PEGS: usize = 10;
+pub const MAX_COLORS: usize = 36;
+
+
+/// The symbols that stand for colors, in order: by default, 1-6 and
+/// so on as in `DIGITS`; for players who think in colors, their
+/// initials, R G B Y O P and on up to ten; for a word game, letters.
+///
+/// Patterns are written in the alphabet of their rules. Digits and
+/// color initials are both read, whichever of the two the rules use.
+///
+/// ```rust
+/// use self::mastermind::gameplay::{Alphabet, Pattern, Rules};
+///
+/// let rules = Rules::new(5, 26).with_alphabet(Alphabet::letters());
+/// let word = rules.parse("CRANE").unwrap();
+/// assert_eq!(format!("{}", word), "crane");
+/// assert_eq!(format!("{}", word.score(rules.parse("nacre").unwrap())), "BWWWW");
+///
+/// let dna = Alphabet::new("ACGT").unwrap();
+/// assert_eq!(dna.color('g'), Some(2));
+/// assert_eq!(Alphabet::new("ACGA"), None);
+/// // Made again, it shares the symbols rather than leak them again.
+/// assert!(std::ptr::eq(Alphabet::new("ACGT").unwrap().as_str(), dna.as_str()));
+///
+/// let colors = Rules::default().with_alphabet(Alphabet::colors());
+/// let code = colors.parse("1123").unwrap();
+/// assert_eq!(format!("{}", code), "RRGB");
+/// assert_eq!(colors.parse("rrgb"), Ok(code));
+/// assert_eq!(format!("{}", "RRGB".parse::<Pattern>().unwrap()), "1123");
+/// ```
+#[derive(PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
+pub struct Alphabet {
+    symbols: &'static str,
+}
+
+/// The symbols of alphabets other than the three built in.
+static INTERNED: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::digits()
+    }
+}
+
+impl Alphabet {
+    pub fn digits() -> Alphabet {
+        Alphabet { symbols: "1234567890ABCDEFGHIJKLMNOPQRSTUVWXYZ" }
+    }
+
+    /// Red, green, blue, yellow, orange, purple, cyan, white, pink
+    /// and brown.
+    pub fn colors() -> Alphabet {
+        Alphabet { symbols: "RGBYOPCWKN" }
+    }
+
+    pub fn letters() -> Alphabet {
+        Alphabet { symbols: "abcdefghijklmnopqrstuvwxyz" }
+    }
+
+    /// Up to `MAX_COLORS` distinct ASCII symbols, other than the
+    /// blank and whitespace. The symbols are leaked the first time
+    /// they're seen, and shared after, however often they're read
+    /// back, e.g. with saved games.
+    pub fn new(symbols: &str) -> Option<Alphabet> {
+        for known in &[Alphabet::digits(), Alphabet::colors(), Alphabet::letters()] {
+            if known.symbols == symbols {
+                return Some(*known);
+            }
+        }
+        let bytes = symbols.as_bytes();
+        let valid = !bytes.is_empty() && bytes.len() <= MAX_COLORS &&
+                    bytes.iter().all(|&b| b.is_ascii_graphic() && b as char != BLANK) &&
+                    bytes.iter().enumerate().all(|(ix, b)| !bytes[..ix].contains(b));
+        if !valid {
+            return None;
+        }
+        let mut interned = INTERNED.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let symbols = match interned.get(symbols) {
+            Some(&symbols) => symbols,
+            None => {
+                let leaked: &'static str = Box::leak(symbols.to_string().into_boxed_str());
+                interned.insert(leaked);
+                leaked
+            }
+        };
+        Some(Alphabet { symbols })
+    }
+
+    /// The symbols, in order of the colors they stand for.
+    pub fn as_str(&self) -> &'static str {
+        self.symbols
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn symbol(&self, color: u8) -> char {
+        self.symbols.as_bytes()[color as usize] as char
+    }
+
+    /// The color a symbol stands for; case doesn't matter unless the
+    /// alphabet has both cases.
+    pub fn color(&self, symbol: char) -> Option<u8> {
+        let bytes = self.symbols.as_bytes();
+        bytes.iter()
+            .position(|&b| b as char == symbol)
+            .or_else(|| bytes.iter().position(|&b| (b as char).eq_ignore_ascii_case(&symbol)))
+            .map(|color| color as u8)
+    }
+}
+
+impl Debug for Alphabet {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        Debug::fmt(self.symbols, fmt)
+    }
+}
+
+
+/// Variations of the game: the number of pegs in a pattern, the
+/// number of colors, whether duplicate colors are allowed in codes
+/// and in guesses, whether holes may be left blank, and what
+/// feedback the codemaker gives.
+///
+/// ```rust
+/// use self::mastermind::gameplay::Rules;
+///
+/// let knuth = Rules::default();
+/// assert_eq!((knuth.pegs, knuth.colors, knuth.codes().count()), (4, 6, 1296));
+///
+/// // Bulls and Cows: 4 digits, no repeats, 10 * 9 * 8 * 7 codes.
+/// let bulls_and_cows = Rules::bulls_and_cows();
+/// assert_eq!(bulls_and_cows.codes().count(), 5040);
+/// assert!(bulls_and_cows.parse("1233").is_err());
+/// assert_eq!(format!("{}", bulls_and_cows.parse("7890").unwrap()), "7890");
+///
+/// // A blank scores like any other color.
+/// let house = Rules::default().with_blanks(false);
+/// assert_eq!(house.codes().count(), 7 * 7 * 7 * 7);
+/// let code = house.parse("1_2_").unwrap();
+/// assert_eq!(format!("{}", code), "1_2_");
+/// assert_eq!(format!("{}", code.score(house.parse("_123").unwrap())), "BWW");
+/// assert!(!house.is_guess(&code));
+///
+/// // Black-peg Mastermind: no white pegs.
+/// let black_only = Rules::default().black_pegs_only();
+/// let (code, guess) = (black_only.parse("1123").unwrap(), black_only.parse("3121").unwrap());
+/// assert_eq!(format!("{}", code.score(guess)), "BBWW");
+/// assert_eq!(format!("{}", black_only.score(code, guess)), "BB");
+/// assert!(black_only.parse_response("BW").is_err());
+/// ```
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Rules {
+    pub pegs: u8,
+    pub colors: u8,
+    /// May a code repeat a color?
+    pub duplicates: bool,
+    /// May a guess repeat a color? Some rules allow it even when
+    /// codes may not.
+    pub duplicate_guesses: bool,
+    /// May a code leave a hole blank? The blank is then scored as
+    /// one more color.
+    pub blanks: bool,
+    /// May a guess leave a hole blank?
+    pub blank_guesses: bool,
+    pub feedback: Feedback,
+    /// Symbols for at least as many colors as there are.
+    pub alphabet: Alphabet,
+}
+
+impl Default for Rules {
+    /// Four pegs of six colors, duplicates allowed.
+    fn default() -> Self {
+        Rules::new(Pattern::size() as u8, CodePeg::colors())
+    }
+}
+
+impl Rules {
+    /// Any number of pegs and colors, with duplicates allowed, as
+    /// long as there are no more than 2^32 patterns.
+    pub fn new(pegs: u8, colors: u8) -> Rules {
+        Rules::checked(pegs, colors).expect("too many or too few pegs or colors")
+    }
+
+    /// As `new`, but None rather than panic if there are too many or
+    /// too few pegs or colors.
+    pub fn checked(pegs: u8, colors: u8) -> Option<Rules> {
+        let fits = pegs >= 1 && pegs as usize <= MAX_PEGS && colors >= 1 && colors as usize <= MAX_COLORS &&
+                   (colors as u64).pow(pegs as u32) <= u32::MAX as u64;
+        if !fits {
+            return None;
+        }
+        Some(Rules {
+            pegs,
+            colors,
+            duplicates: true,
+            duplicate_guesses: true,
+            blanks: false,
+            blank_guesses: false,
+            feedback: Feedback::BlackAndWhite,
+            alphabet: Alphabet::digits(),
+        })
+    }
+
+    /// Forbid duplicate colors in codes and, optionally, in guesses.
+    pub fn no_duplicates(self, in_guesses: bool) -> Rules {
+        assert!(self.colors >= self.pegs, "too few colors for codes without duplicates");
+        Rules {
+            duplicates: false,
+            duplicate_guesses: !in_guesses,
+            ..self
+        }
+    }
+
+    /// Allow blanks in codes and, optionally, in guesses.
+    pub fn with_blanks(self, in_guesses: bool) -> Rules {
+        let with = Rules {
+            blanks: true,
+            blank_guesses: in_guesses,
+            ..self
+        };
+        assert!(with.symbols() as usize <= MAX_COLORS &&
+                (with.symbols() as u64).pow(self.pegs as u32) <= u32::MAX as u64,
+                "too many patterns with blanks");
+        with
+    }
+
+    /// Write patterns in another alphabet.
+    pub fn with_alphabet(self, alphabet: Alphabet) -> Rules {
+        assert!(alphabet.len() >= self.colors as usize, "too few symbols for the colors");
+        Rules { alphabet, ..self }
+    }
+
+    /// Respond with black pegs only.
+    pub fn black_pegs_only(self) -> Rules {
+        Rules { feedback: Feedback::BlackOnly, ..self }
+    }
+
+    /// The permutation game: the code is an arrangement of n colors
+    /// in n positions, guesses are free, and only black pegs are
+    /// given.
+    pub fn permutations(n: u8) -> Rules {
+        Rules::new(n, n).no_duplicates(false).black_pegs_only()
+    }
+
+    /// Bulls and Cows: four of ten digits, without repetition in codes
+    /// or guesses.
+    pub fn bulls_and_cows() -> Rules {
+        Rules::new(4, 10).no_duplicates(true)
+    }
+
+    /// The colors, and the blank, if any, which follows them.
+    pub fn symbols(&self) -> u8 {
+        self.colors + if self.has_blank() { 1 } else { 0 }
+    }
+
+    fn has_blank(&self) -> bool {
+        self.blanks || self.blank_guesses
+    }
+
+    /// Number of patterns of this shape, with or without duplicates
+    /// or blanks.
+    pub fn cardinality(&self) -> u32 {
+        (self.symbols() as u32).pow(self.pegs as u32)
+    }
+
+    /// Construct a pattern of this shape from a lexical index.
+    pub fn ith(&self, lex_ix: u32) -> Pattern {
+        assert!(lex_ix < self.cardinality());
+        Pattern {
+            ix: lex_ix,
+            pegs: self.pegs,
+            symbols: self.symbols(),
+            blank: self.has_blank(),
+            alphabet: self.alphabet,
+        }
+    }
+
+    /// Construct a pattern from code pegs given as color indexes,
+    /// counting from 0; a blank follows the colors.
+    pub fn pattern(&self, code_pegs: &[u8]) -> Pattern {
+        assert_eq!(code_pegs.len(), self.pegs as usize);
+        let symbols = self.symbols();
+        let ix = code_pegs.iter().fold(0, |ix, &c| {
+            assert!(c < symbols);
+            ix * symbols as u32 + c as u32
+        });
+        self.ith(ix)
+    }
+
+    /// All patterns of this shape, whether codes, guesses or neither.
+    pub fn range(&self) -> Box<dyn Iterator<Item = Pattern>> {
+        let rules = *self;
+        Box::new((0..self.cardinality()).map(move |ix| rules.ith(ix)))
+    }
+
+    pub fn is_code(&self, p: &Pattern) -> bool {
+        self.shapes(p) && (self.duplicates || !p.has_duplicates()) && (self.blanks || !p.has_blanks())
+    }
+
+    pub fn is_guess(&self, p: &Pattern) -> bool {
+        self.shapes(p) && (self.duplicate_guesses || !p.has_duplicates()) &&
+        (self.blank_guesses || !p.has_blanks())
+    }
+
+    fn shapes(&self, p: &Pattern) -> bool {
+        p.pegs == self.pegs && p.symbols == self.symbols() && p.blank == self.has_blank() && p.alphabet == self.alphabet
+    }
+
+    /// The codes the codemaker may choose from, in lexical order.
+    pub fn codes(&self) -> Box<dyn Iterator<Item = Pattern>> {
+        let rules = *self;
+        Box::new(self.range().filter(move |p| rules.is_code(p)))
+    }
+
+    /// The patterns the codebreaker may guess, in lexical order.
+    pub fn guesses(&self) -> Box<dyn Iterator<Item = Pattern>> {
+        let rules = *self;
+        Box::new(self.range().filter(move |p| rules.is_guess(p)))
+    }
+
+    /// Choose a code uniformly at random.
+    pub fn random<R: Rng>(&self, rng: &mut R) -> Pattern {
+        let r = Uniform::new(0, self.cardinality());
+        loop {
+            let p = self.ith(r.ind_sample(rng));
+            if self.is_code(&p) {
+                return p;
+            }
+        }
+    }
+
+    /// After Knuth's 1122: half the pegs of one color, half of
+    /// another; or, without duplicates, 1234...
+    pub fn initial_guess(&self) -> Pattern {
+        let code_pegs: Vec<u8> = if self.duplicate_guesses {
+            (0..self.pegs).map(|pos| if pos < self.pegs / 2 || self.colors == 1 { 0 } else { 1 }).collect()
+        } else {
+            (0..self.pegs).collect()
+        };
+        self.pattern(&code_pegs)
+    }
+
+    /// The response is all colored pegs just when the game is won.
+    pub fn is_win(&self, response: &KeyPegs) -> bool {
+        response.blacks == self.pegs
+    }
+
+    /// Could any code give this response to the guess? Not so for
+    /// e.g. BBBW, whatever the guess.
+    pub fn is_response(&self, guess: Pattern, response: KeyPegs) -> bool {
+        self.codes().any(|code| self.score(code, guess) == response)
+    }
+
+    /// Parse a pattern written as by `Display`, e.g. "1123"; with
+    /// digits or color initials, either will do, e.g. "RRGB".
+    pub fn parse(&self, s: &str) -> Result<Pattern, ParseError> {
+        let other = if self.alphabet == Alphabet::digits() {
+            Some(Alphabet::colors())
+        } else if self.alphabet == Alphabet::colors() {
+            Some(Alphabet::digits())
+        } else {
+            None
+        };
+        match (self.parse_in(s, self.alphabet), other) {
+            (Err(ParseError::Symbol(c)), Some(other)) => match self.parse_in(s, other) {
+                Err(ParseError::Symbol(_)) => Err(ParseError::Symbol(c)),
+                parsed => parsed,
+            },
+            (parsed, _) => parsed,
+        }
+    }
+
+    fn parse_in(&self, s: &str, alphabet: Alphabet) -> Result<Pattern, ParseError> {
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() != self.pegs as usize {
+            return Err(ParseError::Length(chars.len()));
+        }
+        let mut code_pegs = vec![];
+        for c in chars {
+            match alphabet.color(c).filter(|&color| color < self.colors) {
+                Some(color) => code_pegs.push(color),
+                None if c == BLANK && self.has_blank() => code_pegs.push(self.colors),
+                None => return Err(ParseError::Symbol(c)),
+            }
+        }
+        let p = self.pattern(&code_pegs);
+        if !self.is_guess(&p) && !self.is_code(&p) {
+            return Err(ParseError::Duplicates);
+        }
+        Ok(p)
+    }
+
+    /// Parse feedback written as by `Display`, e.g. "BWW"; "" means no
+    /// key pegs at all.
+    pub fn parse_response(&self, s: &str) -> Result<KeyPegs, ParseError> {
+        let mut pegs = KeyPegs::new();
+        for c in s.chars() {
+            match c {
+                'B' | 'b' => pegs.blacks += 1,
+                'W' | 'w' if self.feedback == Feedback::BlackAndWhite => pegs.whites += 1,
+                _ => return Err(ParseError::Symbol(c)),
+            }
+        }
+        if pegs.blacks as usize + pegs.whites as usize > self.pegs as usize {
+            return Err(ParseError::Length(s.chars().count()));
+        }
+        Ok(pegs)
+    }
+
+    /// The codemaker's response to a guess, according to the
+    /// feedback rule.
+    pub fn score(&self, code: Pattern, guess: Pattern) -> KeyPegs {
+        self.feedback.score(code, guess)
+    }
+}
+
+
+/// What the codemaker tells the codebreaker about a guess.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Feedback {
+    /// Black pegs for the right color in the right position, white
+    /// pegs for the right color in the wrong position.
+    #[default]
+    BlackAndWhite,
+    /// Only the black pegs: how many positions are right.
+    BlackOnly,
+}
+
+impl Feedback {
+    pub fn score(&self, code: Pattern, guess: Pattern) -> KeyPegs {
+        match *self {
+            Feedback::BlackAndWhite => code.score(guess),
+            Feedback::BlackOnly => KeyPegs::new().blacks(code.blacks(guess)),
+        }
+    }
+}
+
+
+/// A way of responding to guesses, such as key pegs by the `Rules`,
+/// or the per-position marks of `wordle`. The solver's minimax,
+/// entropy and partitioning work with any of them.
+pub trait Scorer {
+    type Response: Copy + Eq + Hash + Ord + Debug + Display;
+
+    fn score(&self, code: Pattern, guess: Pattern) -> Self::Response;
+
+    fn is_win(&self, response: &Self::Response) -> bool;
+
+    /// Responses are numbered from 0 up to this, so that they can be
+    /// counted in a table.
+    fn responses(&self) -> usize;
+
+    fn response_index(&self, response: &Self::Response) -> usize;
+}
+
+impl Scorer for Rules {
+    type Response = KeyPegs;
+
+    fn score(&self, code: Pattern, guess: Pattern) -> KeyPegs {
+        Rules::score(self, code, guess)
+    }
+
+    fn is_win(&self, response: &KeyPegs) -> bool {
+        Rules::is_win(self, response)
+    }
+
+    fn responses(&self) -> usize {
+        (MAX_PEGS + 1) * (MAX_PEGS + 1)
+    }
+
+    fn response_index(&self, response: &KeyPegs) -> usize {
+        response.blacks as usize * (MAX_PEGS + 1) + response.whites as usize
+    }
+}
 
 
 /// A colored or black key peg is placed for each code peg from
@@ -103,13 +617,15 @@ pub struct Pattern(u32);
 /// the wrong position.
 #[derive(Debug)]
 #[derive(PartialEq, Hash, Eq, Copy, Clone, Default)]
+#[derive(PartialOrd, Ord)]
 pub struct KeyPegs {
     blacks: u8,
     whites: u8,
 }
 
 impl KeyPegs {
-    /// If the response is four colored pegs, the game is won.
+    /// If the response is four colored pegs, the game is won, by the
+    /// usual rules; see `Rules::is_win` for others.
     pub fn win(&self) -> bool {
         self.blacks as usize == Pattern::size()
     }
@@ -122,26 +638,43 @@ impl KeyPegs {
     }
 
     pub fn blacks(self, blacks: u8) -> KeyPegs {
-        assert!(blacks as usize + self.whites as usize <= Pattern::size());
-        KeyPegs { blacks: blacks, ..self }
+        assert!(blacks as usize + self.whites as usize <= MAX_PEGS);
+        KeyPegs { blacks, ..self }
     }
 
     pub fn whites(self, whites: u8) -> KeyPegs {
-        assert!(self.blacks as usize + whites as usize <= Pattern::size());
-        KeyPegs { whites: whites, ..self }
+        assert!(self.blacks as usize + whites as usize <= MAX_PEGS);
+        KeyPegs { whites, ..self }
+    }
+
+    pub fn black_count(&self) -> u8 {
+        self.blacks
+    }
+
+    pub fn white_count(&self) -> u8 {
+        self.whites
     }
 }
 
 impl Display for KeyPegs {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        let s = iter::repeat('B')
-            .take(self.blacks as usize)
-            .chain(iter::repeat('W').take(self.whites as usize))
+        let s = iter::repeat_n('B', self.blacks as usize)
+            .chain(iter::repeat_n('W', self.whites as usize))
             .collect::<String>();
         fmt.write_str(&s)
     }
 }
 
+/// Parse feedback written as by `Display`, e.g. "BWW"; "" means no
+/// key pegs at all.
+impl FromStr for KeyPegs {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<KeyPegs, ParseError> {
+        Rules::default().parse_response(s)
+    }
+}
+
 
 impl Pattern {
     /// The codemaker chooses a pattern of four code pegs.
@@ -156,43 +689,85 @@ impl Pattern {
 
     /// Construct a pattern from a lexical index.
     pub fn ith(lex_ix: u32) -> Pattern {
-        assert!(lex_ix <= Pattern::cardinality());
-        Pattern(lex_ix)
+        Rules::default().ith(lex_ix)
     }
 
     pub fn index(&self) -> u32 {
-        self.0
+        self.ix
     }
 
     pub fn range() -> iter::Map<Range<u32>, fn(u32) -> Pattern> {
         (0..Pattern::cardinality()).map(Pattern::ith)
     }
 
+    /// Choose a pattern uniformly at random; see `seeded_rng` for
+    /// reproducible choices.
+    pub fn random<R: Rng>(rng: &mut R) -> Pattern {
+        Rules::default().random(rng)
+    }
+
     /// Construct a Pattern from digits 1-6.
     /// Characters other than 1-6 are treated as '1'.
     // TODO: trade in &str instead
     pub fn from_digits(digits: [char; 4]) -> Pattern {
-        let base = CodePeg::colors() as u32;
-        let digit = |pos: usize| digits[pos].to_digit(base).unwrap_or(1) - 1;
-        let ix = digit(3) + base * (digit(2) + base * (digit(1) + base * digit(0)));
-        Pattern(ix)
+        let symbols = &DIGITS[..CodePeg::colors() as usize];
+        let digit = |pos: usize| symbols.iter().position(|&d| d as char == digits[pos]).unwrap_or(0) as u8;
+        Rules::default().pattern(&[digit(0), digit(1), digit(2), digit(3)])
     }
 
-    /// Decode a Pattern into digits
+    /// Decode a four peg Pattern into digits
     pub fn to_digits(&self) -> [char; 4] {
-        let arb = '1';
-        let mut out = [arb; 4];
-        let mut ith = self.0;
+        let pegs = self.code_pegs();
+        assert_eq!(pegs.len(), 4);
+        let digit = |pos: usize| DIGITS[pegs[pos] as usize] as char;
+        [digit(0), digit(1), digit(2), digit(3)]
+    }
 
-        for exp in 0..Pattern::size() {
-            let remainder = (ith % CodePeg::colors() as u32) as u8;
-            let digit = (('1' as u8) + remainder) as char;
-            ith /= CodePeg::colors() as u32;
-            let pos = (Pattern::size() - 1 - exp) as usize;
-            out[pos] = digit;
+    /// The sum of the digits, as written, if they are all digits.
+    pub fn digit_sum(&self) -> Option<u32> {
+        let (pegs, n) = self.decode();
+        pegs[..n].iter().map(|&c| if self.is_blank(c) { None } else { self.alphabet.symbol(c).to_digit(10) }).sum()
+    }
+
+    /// The colors of the code pegs, counting from 0, in order.
+    pub fn code_pegs(&self) -> Vec<u8> {
+        let (pegs, n) = self.decode();
+        pegs[..n].to_vec()
+    }
+
+    pub(crate) fn decode(&self) -> ([u8; MAX_PEGS], usize) {
+        let mut out = [0; MAX_PEGS];
+        let mut ith = self.ix;
+        let n = self.pegs as usize;
+
+        for exp in 0..n {
+            let remainder = (ith % self.symbols as u32) as u8;
+            ith /= self.symbols as u32;
+            out[n - 1 - exp] = remainder;
         }
 
-        out
+        (out, n)
+    }
+
+    pub(crate) fn is_blank(&self, symbol: u8) -> bool {
+        self.blank && symbol == self.symbols - 1
+    }
+
+    /// Does any color appear more than once? Blanks don't count.
+    pub fn has_duplicates(&self) -> bool {
+        let (pegs, n) = self.decode();
+        let mut seen = [false; MAX_COLORS];
+        pegs[..n].iter().filter(|&&c| !self.is_blank(c)).any(|&c| {
+            let dup = seen[c as usize];
+            seen[c as usize] = true;
+            dup
+        })
+    }
+
+    /// Is any hole left blank?
+    pub fn has_blanks(&self) -> bool {
+        let (pegs, n) = self.decode();
+        pegs[..n].iter().any(|&c| self.is_blank(c))
     }
 
     /// The codemaker provides feedback by placing
@@ -201,66 +776,387 @@ impl Pattern {
     /// the guess which is correct in both color and position. A white key
     /// peg indicates the existence of a correct color code peg placed in
     /// the wrong position.
-    pub fn score(self: &Pattern, guess: Pattern) -> KeyPegs {
-        let s = self.to_digits();
-        let g = guess.to_digits();
-
-        let g_used: Vec<_> = s.iter()
-            .zip(g.iter())
-            .enumerate()
-            .filter(|ea| (ea.1).0 == (ea.1).1)
-            .map(|ea| ea.0)
-            .collect();
-        let blacks = g_used.len();
-
-        let mut s_used = g_used.clone();
+    pub fn score(&self, guess: Pattern) -> KeyPegs {
+        debug_assert!(self.pegs == guess.pegs && self.symbols == guess.symbols);
+        let (s, n) = self.decode();
+        let (g, _) = guess.decode();
 
-        for (gpos, peg) in g.iter().enumerate().take(Pattern::size()) {
-            if !g_used.contains(&gpos) {
-                // Find an unused "self" peg of the same color.
-                let scan = (0..Pattern::size())
-                    .find(|spos| s[*spos] == *peg && !s_used.contains(spos));
-
-                if let Some(spos) = scan {
-                    s_used.push(spos);
-                }
+        // Count colors among the pegs not already matched in position,
+        // then match the guess's unmatched pegs against them.
+        let mut blacks = 0;
+        let mut s_unused = [0u8; MAX_COLORS];
+        for pos in 0..n {
+            if s[pos] == g[pos] {
+                blacks += 1;
+            } else {
+                s_unused[s[pos] as usize] += 1;
             }
         }
-        let whites = s_used.len() - blacks;
+        let mut whites = 0;
+        for pos in 0..n {
+            let color = g[pos] as usize;
+            if s[pos] != g[pos] && s_unused[color] > 0 {
+                s_unused[color] -= 1;
+                whites += 1;
+            }
+        }
+
+        KeyPegs::new().blacks(blacks).whites(whites)
+    }
 
-        KeyPegs::new().blacks(blacks as u8).whites(whites as u8)
+    /// Count the code pegs from the guess which are correct in both
+    /// color and position.
+    pub fn blacks(&self, guess: Pattern) -> u8 {
+        debug_assert!(self.pegs == guess.pegs && self.symbols == guess.symbols);
+        let (s, n) = self.decode();
+        let (g, _) = guess.decode();
+        (0..n).filter(|&pos| s[pos] == g[pos]).count() as u8
     }
 }
 
 
 impl Debug for Pattern {
-    // TODO: refactor w.r.t. Display
-    fn fmt(self: &Pattern, fmt: &mut Formatter) -> fmt::Result {
-        let digits = self.to_digits();
-        fmt.write_fmt(format_args!("{}{}{}{}", digits[0], digits[1], digits[2], digits[3]))
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, fmt)
     }
 }
 
 
 impl Display for Pattern {
-    fn fmt(self: &Pattern, fmt: &mut Formatter) -> fmt::Result {
-        let digits = self.to_digits();
-        fmt.write_fmt(format_args!("{}{}{}{}", digits[0], digits[1], digits[2], digits[3]))
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let digits: String = self.code_pegs()
+            .iter()
+            .map(|&c| if self.is_blank(c) { BLANK } else { self.alphabet.symbol(c) })
+            .collect();
+        fmt.write_str(&digits)
+    }
+}
+
+/// Parse a pattern written as by `Display`, e.g. "1123", or in color
+/// initials, e.g. "RRGB".
+impl FromStr for Pattern {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Pattern, ParseError> {
+        Rules::default().parse(s)
+    }
+}
+
+
+/// Patterns and key pegs are written as in `Display`; anything else
+/// is refused rather than guessed at.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParseError {
+    /// Wrong number of pegs.
+    Length(usize),
+    /// A character that doesn't denote a peg.
+    Symbol(char),
+    /// A repeated color where the rules forbid it.
+    Duplicates,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Length(n) => write!(fmt, "wrong number of pegs: {}", n),
+            ParseError::Symbol(c) => write!(fmt, "not a peg: {:?}", c),
+            ParseError::Duplicates => fmt.write_str("duplicate colors not allowed"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+
+/// A game as played: the rules, the secret, if known, and each guess
+/// together with the feedback it got. Headers carry metadata such as
+/// player names; see the `record` module.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct GameRecord {
+    pub rules: Rules,
+    pub headers: Vec<(String, String)>,
+    pub secret: Option<Pattern>,
+    pub turns: Vec<(Pattern, KeyPegs)>,
+}
+
+impl GameRecord {
+    /// A game by the usual rules.
+    pub fn new(secret: Option<Pattern>) -> GameRecord {
+        GameRecord::with_rules(Rules::default(), secret)
+    }
+
+    pub fn with_rules(rules: Rules, secret: Option<Pattern>) -> GameRecord {
+        GameRecord {
+            rules,
+            headers: vec![],
+            secret,
+            turns: vec![],
+        }
+    }
+
+    pub fn push(&mut self, guess: Pattern, response: KeyPegs) {
+        self.turns.push((guess, response));
+    }
+
+    /// The game is won once some response is all colored pegs.
+    pub fn won(&self) -> bool {
+        self.turns.iter().any(|&(_, response)| self.rules.is_win(&response))
     }
 }
 
+
+/// All randomness, from the codemaker's secret to the codebreaker's
+/// tie-breaking, is drawn from an `Rng` supplied by the caller, so
+/// that a game can be replayed from its seed.
+///
+/// ```rust
+/// use self::mastermind::gameplay::{Pattern, seeded_rng};
+///
+/// let secret = Pattern::random(&mut seeded_rng(2015));
+/// assert_eq!(Pattern::random(&mut seeded_rng(2015)), secret);
+/// ```
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::from_seed(&[seed as usize, (seed >> 32) as usize][..])
+}
+
+
 /// ... a shield at one end covering a row of four large holes ...
-pub type Shield = Box<Fn(&Pattern) -> KeyPegs>;
+pub type Shield = Box<dyn Fn(&Pattern) -> KeyPegs>;
 
 /// Encapsulate a code in a function that provides feedback w.r.t. the code.
 pub fn shield(code: Pattern) -> Shield {
     Box::new(move |guess: &Pattern| code.score(*guess))
 }
 
+/// As `shield`, with feedback according to the rules.
+pub fn shield_with(rules: Rules, code: Pattern) -> Shield {
+    Box::new(move |guess: &Pattern| rules.score(code, *guess))
+}
+
+
+/// Patterns of the usual four pegs and six colors serialize as their
+/// digits, e.g. "1123"; others along with their shape, e.g.
+/// `{"pegs": 5, "colors": 8, "blank": false, "alphabet": ..., "code":
+/// "12345"}`, so as to read back the same. Key pegs serialize
+/// as e.g. "BWW", though `{"blacks": 1, "whites": 2}` is accepted too.
+#[cfg(feature = "serde")]
+mod serialization {
+    use std::fmt;
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+    use serde::de::{self, Visitor, MapAccess};
+
+    use super::{Alphabet, Feedback, GameRecord, KeyPegs, MAX_PEGS, ParseError, Pattern, Rules};
+
+    /// A pattern of some other shape, and the shape.
+    #[derive(Serialize, Deserialize)]
+    struct Shaped {
+        pegs: u8,
+        colors: u8,
+        blank: bool,
+        alphabet: Alphabet,
+        code: String,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Written {
+        Usual(String),
+        Shaped(Shaped),
+    }
+
+    impl Shaped {
+        /// Rules under which the code reads back as written.
+        fn rules(&self) -> Option<Rules> {
+            let rules = Rules::checked(self.pegs, self.colors)?;
+            if self.alphabet.len() < self.colors as usize {
+                return None;
+            }
+            if self.blank {
+                // One more symbol for the blank must fit as well.
+                Rules::checked(self.pegs, self.colors.checked_add(1)?)?;
+                return Some(rules.with_alphabet(self.alphabet).with_blanks(true));
+            }
+            Some(rules.with_alphabet(self.alphabet))
+        }
+    }
+
+    impl Serialize for Alphabet {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(self.symbols)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Alphabet {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Alphabet, D::Error> {
+            let s = String::deserialize(deserializer)?;
+            Alphabet::new(&s).ok_or_else(|| de::Error::custom("not an alphabet"))
+        }
+    }
+
+    /// Rules as written, to be checked before they're trusted.
+    #[derive(Deserialize)]
+    #[serde(rename = "Rules")]
+    struct WrittenRules {
+        pegs: u8,
+        colors: u8,
+        duplicates: bool,
+        duplicate_guesses: bool,
+        blanks: bool,
+        blank_guesses: bool,
+        feedback: Feedback,
+        alphabet: Alphabet,
+    }
+
+    impl<'de> Deserialize<'de> for Rules {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rules, D::Error> {
+            let w = WrittenRules::deserialize(deserializer)?;
+            let rules = Rules {
+                duplicates: w.duplicates,
+                duplicate_guesses: w.duplicate_guesses,
+                blanks: w.blanks,
+                blank_guesses: w.blank_guesses,
+                feedback: w.feedback,
+                alphabet: w.alphabet,
+                ..Rules::checked(w.pegs, w.colors).ok_or_else(|| de::Error::custom("too many or too few pegs or colors"))?
+            };
+            if Rules::checked(rules.pegs, rules.symbols()).is_none() {
+                return Err(de::Error::custom("too many patterns with blanks"));
+            }
+            if rules.alphabet.len() < rules.colors as usize {
+                return Err(de::Error::custom("too few symbols for the colors"));
+            }
+            if !rules.duplicates && rules.colors < rules.pegs {
+                return Err(de::Error::custom("too few colors for codes without duplicates"));
+            }
+            Ok(rules)
+        }
+    }
+
+    impl Serialize for Pattern {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let usual = Rules::default();
+            if self.pegs == usual.pegs && self.symbols == usual.symbols() && self.alphabet == usual.alphabet && !self.blank {
+                return serializer.collect_str(self);
+            }
+            Written::Shaped(Shaped {
+                pegs: self.pegs,
+                colors: self.symbols - if self.blank { 1 } else { 0 },
+                blank: self.blank,
+                alphabet: self.alphabet,
+                code: self.to_string(),
+            }).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Pattern {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Pattern, D::Error> {
+            match Written::deserialize(deserializer)? {
+                Written::Usual(code) => code.parse().map_err(de::Error::custom),
+                Written::Shaped(shaped) => {
+                    let rules = shaped.rules().ok_or_else(|| de::Error::custom("no such shape of pattern"))?;
+                    rules.parse(&shaped.code).map_err(de::Error::custom)
+                }
+            }
+        }
+    }
+
+    impl Serialize for KeyPegs {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    struct KeyPegsVisitor;
+
+    impl<'de> Visitor<'de> for KeyPegsVisitor {
+        type Value = KeyPegs;
+
+        fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            fmt.write_str("key pegs such as \"BWW\" or {\"blacks\": 1, \"whites\": 2}")
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> Result<KeyPegs, E> {
+            // Up to as many pegs as any rules allow; the rules, if at
+            // hand, may allow fewer.
+            let mut pegs = KeyPegs::new();
+            for c in s.chars() {
+                match c {
+                    'B' | 'b' => pegs.blacks += 1,
+                    'W' | 'w' => pegs.whites += 1,
+                    _ => return Err(E::custom(ParseError::Symbol(c))),
+                }
+                if pegs.blacks as usize + pegs.whites as usize > MAX_PEGS {
+                    return Err(E::custom("too many key pegs"));
+                }
+            }
+            Ok(pegs)
+        }
+
+        fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<KeyPegs, M::Error> {
+            let (mut blacks, mut whites) = (0u8, 0u8);
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "blacks" => blacks = map.next_value()?,
+                    "whites" => whites = map.next_value()?,
+                    _ => return Err(de::Error::unknown_field(&key, &["blacks", "whites"])),
+                }
+            }
+            if blacks as usize + whites as usize > MAX_PEGS {
+                return Err(de::Error::custom("too many key pegs"));
+            }
+            Ok(KeyPegs { blacks, whites })
+        }
+    }
+
+    impl<'de> Deserialize<'de> for KeyPegs {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<KeyPegs, D::Error> {
+            deserializer.deserialize_any(KeyPegsVisitor)
+        }
+    }
+
+    /// Patterns and responses as written under the rules, which are
+    /// left out if they're the usual ones.
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "GameRecord")]
+    struct WrittenGame {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rules: Option<Rules>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        headers: Vec<(String, String)>,
+        secret: Option<String>,
+        turns: Vec<(String, String)>,
+    }
+
+    impl Serialize for GameRecord {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            WrittenGame {
+                rules: Some(self.rules).filter(|&rules| rules != Rules::default()),
+                headers: self.headers.clone(),
+                secret: self.secret.map(|p| p.to_string()),
+                turns: self.turns.iter().map(|&(guess, response)| (guess.to_string(), response.to_string())).collect(),
+            }.serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for GameRecord {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<GameRecord, D::Error> {
+            let written = WrittenGame::deserialize(deserializer)?;
+            let rules = written.rules.unwrap_or_default();
+            let pattern = |p: &str| rules.parse(p).map_err(de::Error::custom);
+            let mut game = GameRecord::with_rules(rules, written.secret.as_deref().map(pattern).transpose()?);
+            game.headers = written.headers;
+            for (guess, response) in &written.turns {
+                game.push(pattern(guess)?, rules.parse_response(response).map_err(de::Error::custom)?);
+            }
+            Ok(game)
+        }
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
-    use super::{Pattern, KeyPegs};
+    use super::{Pattern, KeyPegs, ParseError, Rules};
 
     #[test]
     fn scoring() {
@@ -277,4 +1173,127 @@ mod tests {
         let t1 = s.score(g);
         assert_eq!(t1, KeyPegs::new().blacks(4).whites(0));
     }
+
+    #[test]
+    fn scoring_other_rules() {
+        let rules = Rules::new(5, 8);
+        let (s, g) = (rules.parse("12345").unwrap(), rules.parse("82315").unwrap());
+        assert_eq!(s.score(g), KeyPegs::new().blacks(3).whites(1));
+        assert!(!rules.is_win(&s.score(g)));
+        assert!(rules.is_win(&s.score(s)));
+        assert_eq!(rules.initial_guess(), rules.parse("11222").unwrap());
+    }
+
+    #[test]
+    fn impossible_responses() {
+        let rules = Rules::default();
+        let guess = rules.parse("1234").unwrap();
+        assert!(rules.is_response(guess, KeyPegs::new().blacks(2).whites(2)));
+        assert!(!rules.is_response(guess, KeyPegs::new().blacks(3).whites(1)));
+        assert!(!rules.is_response(rules.parse("1111").unwrap(), KeyPegs::new().whites(1)));
+    }
+
+    #[test]
+    fn no_duplicates() {
+        let rules = Rules::new(4, 6).no_duplicates(false);
+        assert_eq!(rules.codes().count(), 6 * 5 * 4 * 3);
+        assert_eq!(rules.guesses().count(), 1296);
+        let p = rules.parse("1123").unwrap();
+        assert!(rules.is_guess(&p) && !rules.is_code(&p));
+        assert_eq!(rules.initial_guess(), rules.parse("1122").unwrap());
+
+        let strict = rules.no_duplicates(true);
+        assert_eq!(strict.parse("1123"), Err(ParseError::Duplicates));
+        assert_eq!(strict.initial_guess(), strict.parse("1234").unwrap());
+        assert!(strict.guesses().all(|p| !p.has_duplicates()));
+    }
+
+    #[test]
+    fn blanks() {
+        let rules = Rules::default().no_duplicates(true).with_blanks(true);
+        let p = rules.parse("1__2").unwrap();
+        assert!(!p.has_duplicates() && p.has_blanks());
+        assert!(rules.is_code(&p) && rules.is_guess(&p));
+        assert_eq!(p.score(rules.parse("_1_3").unwrap()), KeyPegs::new().blacks(1).whites(2));
+        assert_eq!(rules.initial_guess(), rules.parse("1234").unwrap());
+
+        assert_eq!(Rules::default().parse("1__2"), Err(ParseError::Symbol('_')));
+        let codes_only = Rules::default().with_blanks(false);
+        assert!(!codes_only.is_guess(&codes_only.parse("1__2").unwrap()));
+        assert_eq!(codes_only.guesses().count(), 1296);
+    }
+
+    #[test]
+    fn random() {
+        use super::seeded_rng;
+
+        let rng = &mut seeded_rng(1977);
+        let codes: Vec<Pattern> = (0..100).map(|_| Pattern::random(rng)).collect();
+        assert!(codes.iter().all(|p| p.index() < Pattern::cardinality()));
+
+        let again = &mut seeded_rng(1977);
+        assert_eq!((0..100).map(|_| Pattern::random(again)).collect::<Vec<_>>(), codes);
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!("1123".parse(), Ok(Pattern::from_digits(['1', '1', '2', '3'])));
+        assert_eq!("112".parse::<Pattern>(), Err(ParseError::Length(3)));
+        assert_eq!("1173".parse::<Pattern>(), Err(ParseError::Symbol('7')));
+        assert_eq!("6666".parse(), Ok(Pattern::from_digits(['6', '6', '6', '6'])));
+
+        assert_eq!("BWW".parse(), Ok(KeyPegs::new().blacks(1).whites(2)));
+        assert_eq!("".parse(), Ok(KeyPegs::new()));
+        assert_eq!("BBBBW".parse::<KeyPegs>(), Err(ParseError::Length(5)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        extern crate serde_json;
+        use super::GameRecord;
+
+        let p = Pattern::from_digits(['1', '1', '2', '3']);
+        assert_eq!(serde_json::to_string(&p).unwrap(), "\"1123\"");
+        assert_eq!(serde_json::from_str::<Pattern>("\"1123\"").unwrap(), p);
+        assert!(serde_json::from_str::<Pattern>("\"1183\"").is_err());
+
+        // Other shapes read back under their own rules.
+        let bulls_and_cows = Rules::bulls_and_cows();
+        let p0 = bulls_and_cows.parse("7890").unwrap();
+        let json = serde_json::to_string(&p0).unwrap();
+        assert_eq!(serde_json::from_str::<Pattern>(&json).unwrap(), p0);
+        let blanks = Rules::new(5, 8).with_blanks(false);
+        let p1 = blanks.parse("1_8_5").unwrap();
+        assert_eq!(serde_json::from_str::<Pattern>(&serde_json::to_string(&p1).unwrap()).unwrap(), p1);
+        // Four pegs of five colors and a blank: as many symbols as the
+        // usual shape, but not the same patterns.
+        let blanks = Rules::new(4, 5).with_blanks(false);
+        for written in &["1_23", "1123"] {
+            let p2 = blanks.parse(written).unwrap();
+            assert_eq!(serde_json::from_str::<Pattern>(&serde_json::to_string(&p2).unwrap()).unwrap(), p2);
+        }
+
+        let b1w2 = KeyPegs::new().blacks(1).whites(2);
+        assert_eq!(serde_json::to_string(&b1w2).unwrap(), "\"BWW\"");
+        assert_eq!(serde_json::from_str::<KeyPegs>("\"BWW\"").unwrap(), b1w2);
+        assert_eq!(serde_json::from_str::<KeyPegs>(r#"{"blacks": 1, "whites": 2}"#).unwrap(),
+                   b1w2);
+
+        let mut game = GameRecord::new(Some(p));
+        let guess = Pattern::from_digits(['1', '1', '2', '2']);
+        game.push(guess, p.score(guess));
+        game.push(p, p.score(p));
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(json, r#"{"secret":"1123","turns":[["1122","BBB"],["1123","BBBB"]]}"#);
+        assert_eq!(serde_json::from_str::<GameRecord>(&json).unwrap(), game);
+
+        let rules = Rules::new(5, 8);
+        let code = rules.parse("12345").unwrap();
+        let mut game = GameRecord::with_rules(rules, Some(code));
+        game.push(code, rules.score(code, code));
+        assert!(game.won());
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(serde_json::from_str::<GameRecord>(&json).unwrap(), game);
+    }
 }
@@ -0,0 +1,233 @@
+//! Grand Mastermind: each peg has a color and a shape, and the
+//! codemaker answers with four kinds of key peg.
+//!
+//! A peg with several attributes is still one symbol of a `Pattern`,
+//! numbered as its attribute values are, first attribute first; five
+//! colors and five shapes make 25 symbols. So the usual patterns and
+//! sets serve, and only scoring differs:
+//!
+//!  - black: right peg, right position;
+//!  - white: right peg, wrong position, counted as usual;
+//!  - for each attribute, in a position without a black peg, a key
+//!    for that attribute alone being right: in Grand Mastermind,
+//!    right color (C) or right shape (S).
+//!
+//! The solver's `solve` plays it by minimax as any other `Scorer`,
+//! though with 390625 codes, the full game is best played from a
+//! restricted set of guesses.
+//!
+//! ```rust
+//! use self::mastermind::grand::{Attributes, Grand};
+//! use self::mastermind::gameplay::Scorer;
+//!
+//! let grand = Grand::new(Attributes::grand(), 4);
+//! assert_eq!(grand.rules().codes().count(), 390625);
+//!
+//! // (color, shape) for each peg
+//! let code = grand.pattern(&[[0, 0], [1, 2], [2, 2], [3, 4]]);
+//! let guess = grand.pattern(&[[0, 0], [2, 2], [2, 3], [4, 0]]);
+//! assert_eq!(format!("{}", grand.score(code, guess)), "BWCS");
+//! ```
+
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+
+use gameplay::{Pattern, Rules, Scorer, MAX_COLORS};
+
+
+pub const MAX_ATTRIBUTES: usize = 4;
+
+/// Keys for attributes, in order: color, shape, then any others.
+const KEYS: &[u8] = b"CSXY";
+
+
+/// How many values each attribute of a peg may take.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Attributes {
+    sizes: [u8; MAX_ATTRIBUTES],
+    len: u8,
+}
+
+impl Attributes {
+    pub fn new(sizes: &[u8]) -> Attributes {
+        assert!(!sizes.is_empty() && sizes.len() <= MAX_ATTRIBUTES, "too many or too few attributes");
+        let mut attributes = Attributes {
+            sizes: [1; MAX_ATTRIBUTES],
+            len: sizes.len() as u8,
+        };
+        attributes.sizes[..sizes.len()].copy_from_slice(sizes);
+        assert!(attributes.symbols() as usize <= MAX_COLORS, "too many kinds of peg");
+        attributes
+    }
+
+    /// Five colors and five shapes.
+    pub fn grand() -> Attributes {
+        Attributes::new(&[5, 5])
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// How many kinds of peg there are.
+    pub fn symbols(&self) -> u8 {
+        self.sizes.iter().product()
+    }
+
+    /// The peg with the given attribute values.
+    pub fn symbol(&self, values: &[u8]) -> u8 {
+        assert_eq!(values.len(), self.len());
+        values.iter().zip(&self.sizes).fold(0, |symbol, (&value, &size)| {
+            assert!(value < size);
+            symbol * size + value
+        })
+    }
+
+    /// The value of each attribute of a peg.
+    pub fn values(&self, symbol: u8) -> [u8; MAX_ATTRIBUTES] {
+        let mut values = [0; MAX_ATTRIBUTES];
+        let mut rest = symbol;
+        for a in (0..self.len()).rev() {
+            values[a] = rest % self.sizes[a];
+            rest /= self.sizes[a];
+        }
+        values
+    }
+}
+
+
+/// Black and white key pegs, and a count of keys for each attribute.
+#[derive(PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord, Default)]
+pub struct Keys {
+    pub blacks: u8,
+    pub whites: u8,
+    pub partial: [u8; MAX_ATTRIBUTES],
+}
+
+impl Display for Keys {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let mut s = String::new();
+        s.extend((0..self.blacks).map(|_| 'B'));
+        s.extend((0..self.whites).map(|_| 'W'));
+        for (a, &n) in self.partial.iter().enumerate() {
+            s.extend((0..n).map(|_| KEYS[a] as char));
+        }
+        fmt.write_str(&s)
+    }
+}
+
+impl Debug for Keys {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, fmt)
+    }
+}
+
+
+/// Scoring for pegs with several attributes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Grand {
+    attributes: Attributes,
+    rules: Rules,
+}
+
+impl Grand {
+    pub fn new(attributes: Attributes, pegs: u8) -> Grand {
+        Grand {
+            attributes,
+            rules: Rules::new(pegs, attributes.symbols()),
+        }
+    }
+
+    /// The patterns of the game, with a symbol for each kind of peg.
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn attributes(&self) -> Attributes {
+        self.attributes
+    }
+
+    /// The pattern whose pegs have the given attribute values.
+    pub fn pattern<P: AsRef<[u8]>>(&self, pegs: &[P]) -> Pattern {
+        let symbols: Vec<u8> = pegs.iter().map(|values| self.attributes.symbol(values.as_ref())).collect();
+        self.rules.pattern(&symbols)
+    }
+}
+
+impl Scorer for Grand {
+    type Response = Keys;
+
+    fn score(&self, code: Pattern, guess: Pattern) -> Keys {
+        let (c, n) = code.decode();
+        let (g, _) = guess.decode();
+        let key_pegs = code.score(guess);
+
+        let mut partial = [0; MAX_ATTRIBUTES];
+        for pos in (0..n).filter(|&pos| c[pos] != g[pos]) {
+            let (cv, gv) = (self.attributes.values(c[pos]), self.attributes.values(g[pos]));
+            for a in 0..self.attributes.len() {
+                if cv[a] == gv[a] {
+                    partial[a] += 1;
+                }
+            }
+        }
+        Keys {
+            blacks: key_pegs.black_count(),
+            whites: key_pegs.white_count(),
+            partial,
+        }
+    }
+
+    fn is_win(&self, response: &Keys) -> bool {
+        response.blacks == self.rules.pegs
+    }
+
+    fn responses(&self) -> usize {
+        (self.rules.pegs as usize + 1).pow(2 + self.attributes.len() as u32)
+    }
+
+    fn response_index(&self, response: &Keys) -> usize {
+        let base = self.rules.pegs as usize + 1;
+        response.partial[..self.attributes.len()]
+            .iter()
+            .fold(response.blacks as usize * base + response.whites as usize,
+                  |ix, &n| ix * base + n as usize)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{Rules, Scorer};
+    use solver::{PatternSet, partition, solve};
+    use super::{Attributes, Grand};
+
+    #[test]
+    fn attributes() {
+        let grand = Attributes::grand();
+        assert_eq!(grand.symbols(), 25);
+        assert_eq!(grand.symbol(&[2, 3]), 13);
+        assert_eq!(&grand.values(13)[..2], &[2, 3]);
+    }
+
+    #[test]
+    fn play_small() {
+        let grand = Grand::new(Attributes::new(&[3, 2]), 3);
+        let codes = PatternSet::codes(grand.rules());
+        let guess = grand.rules().initial_guess();
+
+        // Partial keys tell apart what key pegs alone can't.
+        let plain = Rules::new(3, 6);
+        assert!(partition(&grand, guess, &codes).len() > partition(&plain, guess, &codes).len());
+
+        for code in grand.rules().codes().step_by(7) {
+            let guesses = solve(&grand, codes.clone(), &codes, &|g| grand.score(code, *g));
+            assert_eq!(guesses.last(), Some(&code));
+            assert!(guesses.len() <= 4);
+        }
+    }
+}
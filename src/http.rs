@@ -0,0 +1,483 @@
+//! A JSON API over HTTP, for web frontends: scoring, hints and the
+//! consistent codes for a history of turns, and games played against
+//! a secret kept on the server.
+//!
+//! Every endpoint takes a POST with a JSON body and answers with JSON:
+//!
+//!  - `/score {"secret": "1123", "guess": "1234"}`: `{"response": "BWW"}`.
+//!  - `/hint {"history": [["1122", "BW"]]}`: the solver's next
+//!    guess, and how many codes remain, e.g.
+//!    `{"guess": "1134", "remaining": 208}`.
+//!  - `/consistent {"history": [...]}`: the codes that fit the
+//!    history, e.g. `{"count": 1, "codes": ["3344"]}`.
+//!  - `/game {"player": "alice"}`: start a game, as with `serve`;
+//!    `{"id": "5f0c62e1a4d3b7e9", "rows": 12}`, its token.
+//!  - `/game/5f0c62e1a4d3b7e9/guess {"guess": "1122"}`: e.g.
+//!    `{"response": "BW", "turns": 1, "won": false, "secret": null}`,
+//!    the secret revealed only when the game is lost.
+//!
+//! Patterns are written as the rules write them; responses as by
+//! `KeyPegs`, `""` or `"-"` for none. Anything amiss is answered
+//! with a 4xx status and `{"error": reason}`, as is a request with too
+//! long a line or body; a client that stalls is hung up on.
+//!
+//! Built with the `http` feature.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::Rules;
+//! use self::mastermind::http::Api;
+//!
+//! let mut api = Api::new(Rules::default(), 12, 7);
+//! let (status, body) = api.handle("POST", "/score", r#"{"secret": "1123", "guess": "1234"}"#);
+//! assert_eq!((status, body.as_str()), (200, r#"{"response":"BWW"}"#));
+//! let (status, body) = api.handle("POST", "/score", r#"{"secret": "1123", "guess": "12"}"#);
+//! assert_eq!(status, 400);
+//! assert!(body.starts_with(r#"{"error":"guess: "#));
+//! ```
+
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{Sender, channel};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json;
+use serde_json::Value;
+
+use gameplay::{KeyPegs, Pattern, Rules};
+use server::{PlayError, Server};
+use solver::{PatternSet, Solver};
+
+
+/// Larger request bodies are refused.
+const MAX_BODY: usize = 1 << 20;
+/// As are longer request lines and header lines, and more headers.
+const MAX_LINE: usize = 8 << 10;
+const MAX_HEADERS: usize = 100;
+/// A client that sends nothing for so long is hung up on.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+
+/// A request's method, path and body, and where to send the status
+/// and body of the answer.
+type Request = (String, String, String, Sender<(u16, String)>);
+
+
+#[derive(Deserialize)]
+struct ScoreRequest {
+    secret: String,
+    guess: String,
+}
+
+#[derive(Deserialize)]
+struct HistoryRequest {
+    history: Vec<(String, String)>,
+}
+
+#[derive(Deserialize, Default)]
+struct GameRequest {
+    player: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GuessRequest {
+    guess: String,
+}
+
+
+/// A status other than 200 OK, and why.
+type Refusal = (u16, String);
+
+
+/// An endpoint, as named by the method and path.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Endpoint<'a> {
+    Score,
+    Hint,
+    Consistent,
+    NewGame,
+    Guess(&'a str),
+}
+
+impl<'a> Endpoint<'a> {
+    fn of(method: &str, path: &'a str) -> Result<Endpoint<'a>, Refusal> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let endpoint = match segments.as_slice() {
+            ["score"] => Endpoint::Score,
+            ["hint"] => Endpoint::Hint,
+            ["consistent"] => Endpoint::Consistent,
+            ["game"] => Endpoint::NewGame,
+            ["game", id, "guess"] => Endpoint::Guess(id),
+            _ => return Err((404, format!("no such endpoint: {}", path))),
+        };
+        match method {
+            "POST" => Ok(endpoint),
+            _ => Err((405, format!("{} not allowed; POST instead", method))),
+        }
+    }
+
+    /// Whether the games are needed, or only the rules.
+    fn plays(&self) -> bool {
+        matches!(*self, Endpoint::NewGame | Endpoint::Guess(_))
+    }
+}
+
+
+/// The endpoints, and the games started by them.
+pub struct Api {
+    rules: Rules,
+    rows: usize,
+    games: Server,
+}
+
+impl Api {
+    /// Patterns by the rules; games of as many rows, with secrets
+    /// chosen as the seed determines.
+    pub fn new(rules: Rules, rows: usize, seed: u64) -> Api {
+        Api {
+            rules,
+            rows,
+            games: Server::new(rules, rows, seed),
+        }
+    }
+
+    /// The status and JSON body in answer to a request.
+    pub fn handle(&mut self, method: &str, path: &str, body: &str) -> (u16, String) {
+        let answer = Endpoint::of(method, path).and_then(|endpoint| match endpoint {
+            Endpoint::NewGame => self.new_game(body),
+            Endpoint::Guess(id) => self.guess(id, body),
+            _ => Codes(self.rules).answer(endpoint, body),
+        });
+        written(answer)
+    }
+
+    fn new_game(&mut self, body: &str) -> Result<Value, Refusal> {
+        // The body may be left out altogether.
+        let request: GameRequest = if body.trim().is_empty() { GameRequest::default() } else { parse_body(body)? };
+        let token = self.games.start(request.player.as_deref().unwrap_or("player"));
+        Ok(json!({ "id": token.to_string(), "rows": self.rows }))
+    }
+
+    fn guess(&mut self, id: &str, body: &str) -> Result<Value, Refusal> {
+        let token = id.parse().map_err(|_| (404, format!("no game {}", id)))?;
+        let request: GuessRequest = parse_body(body)?;
+        let turn = self.games.guess(token, &request.guess).map_err(|e| match e {
+            PlayError::NoGame(_) => (404, e.to_string()),
+            PlayError::GameOver => (409, e.to_string()),
+            PlayError::Pattern(_) | PlayError::NotAllowed => (400, format!("guess: {}", e)),
+        })?;
+        Ok(json!({
+            "response": turn.response.to_string(),
+            "turns": turn.turns,
+            "won": turn.won,
+            "secret": turn.revealed.map(|secret| secret.to_string()),
+        }))
+    }
+}
+
+
+/// The endpoints that need only the rules: scoring, hints and the
+/// consistent codes. Unlike the games, these can be answered on any
+/// thread, so that a slow hint holds up no one else.
+#[derive(Debug, Clone, Copy)]
+struct Codes(Rules);
+
+impl Codes {
+    fn answer(&self, endpoint: Endpoint, body: &str) -> Result<Value, Refusal> {
+        match endpoint {
+            Endpoint::Score => self.score(body),
+            Endpoint::Hint => self.hint(body),
+            Endpoint::Consistent => self.consistent(body),
+            Endpoint::NewGame | Endpoint::Guess(_) => unreachable!("games are the Api's"),
+        }
+    }
+
+    fn score(&self, body: &str) -> Result<Value, Refusal> {
+        let request: ScoreRequest = parse_body(body)?;
+        let secret = self.pattern("secret", &request.secret)?;
+        let guess = self.pattern("guess", &request.guess)?;
+        Ok(json!({ "response": self.0.score(secret, guess).to_string() }))
+    }
+
+    fn hint(&self, body: &str) -> Result<Value, Refusal> {
+        let mut breaker = self.observed(body)?;
+        // Once only one code fits, guess it, not a code that tells it.
+        let guess = match breaker.s.len() {
+            1 => breaker.s.iter().next().expect("one code"),
+            _ => breaker.next_guess(),
+        };
+        Ok(json!({ "guess": guess.to_string(), "remaining": breaker.s.len() }))
+    }
+
+    fn consistent(&self, body: &str) -> Result<Value, Refusal> {
+        let breaker = self.observed(body)?;
+        let codes: Vec<String> = breaker.s.iter().map(|code| code.to_string()).collect();
+        Ok(json!({ "count": codes.len(), "codes": codes }))
+    }
+
+    fn pattern(&self, field: &str, written: &str) -> Result<Pattern, Refusal> {
+        self.0.parse(written).map_err(|e| (400, format!("{}: {}", field, e)))
+    }
+
+    /// A solver that has observed the history in the body; refused
+    /// if no code fits it.
+    fn observed(&self, body: &str) -> Result<Solver, Refusal> {
+        let request: HistoryRequest = parse_body(body)?;
+        let mut breaker = Solver::observing(PatternSet::codes(self.0));
+        for (turn, (guess, response)) in request.history.iter().enumerate() {
+            let guess = self.pattern(&format!("history[{}]", turn), guess)?;
+            let response = self.response(&format!("history[{}]", turn), response)?;
+            breaker.observe(guess, response);
+        }
+        if breaker.s.is_empty() {
+            return Err((422, "no code fits the history".to_string()));
+        }
+        Ok(breaker)
+    }
+
+    fn response(&self, field: &str, written: &str) -> Result<KeyPegs, Refusal> {
+        let written = if written == "-" { "" } else { written };
+        self.0.parse_response(written).map_err(|e| (400, format!("{}: {}", field, e)))
+    }
+}
+
+/// The status and JSON body for an answer or a refusal.
+fn written(answer: Result<Value, Refusal>) -> (u16, String) {
+    match answer {
+        Ok(value) => (200, value.to_string()),
+        Err((status, reason)) => (status, json!({ "error": reason }).to_string()),
+    }
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, Refusal> {
+    serde_json::from_str(body).map_err(|e| (400, format!("bad request body: {}", e)))
+}
+
+
+/// Answer requests as they come until the listener fails. One
+/// thread holds the games, as with `server::serve`; each connection
+/// has a thread that reads its request, answers it, with the games'
+/// help if it's about a game, and closes the connection.
+pub fn serve(listener: TcpListener, rules: Rules, rows: usize, seed: u64) -> io::Result<()> {
+    let (requests, incoming) = channel::<Request>();
+    thread::spawn(move || {
+        let mut api = Api::new(rules, rows, seed);
+        for (method, path, body, reply) in incoming {
+            let _ = reply.send(api.handle(&method, &path, &body));
+        }
+    });
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let requests = requests.clone();
+        // A client's mistake or hang-up is no reason to stop serving.
+        thread::spawn(move || {
+            let _ = exchange(stream, Codes(rules), &requests);
+        });
+    }
+    Ok(())
+}
+
+/// Read one request, answer it or pass it on, and write the answer.
+fn exchange(stream: TcpStream, codes: Codes, requests: &Sender<Request>) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    let request_line = match read_line(&mut reader)? {
+        Some(line) => line,
+        None => return reply(&mut writer, 414, &json!({ "error": "request line too long" }).to_string()),
+    };
+    let words: Vec<&str> = request_line.split_whitespace().collect();
+    let (method, path) = match words.as_slice() {
+        [method, path, _version] => (*method, *path),
+        _ => return reply(&mut writer, 400, &json!({ "error": "bad request line" }).to_string()),
+    };
+
+    let mut length = 0;
+    for headers in 0.. {
+        let header = match read_line(&mut reader)? {
+            Some(header) => header,
+            None => return reply(&mut writer, 431, &json!({ "error": "header line too long" }).to_string()),
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+        if headers == MAX_HEADERS {
+            return reply(&mut writer, 431, &json!({ "error": "too many headers" }).to_string());
+        }
+        let mut parts = header.splitn(2, ':');
+        let (name, value) = (parts.next().unwrap_or(""), parts.next().unwrap_or("").trim());
+        if name.eq_ignore_ascii_case("content-length") {
+            length = match value.parse() {
+                Ok(length) => length,
+                Err(_) => return reply(&mut writer, 400, &json!({ "error": "bad Content-Length" }).to_string()),
+            };
+        }
+    }
+    if length > MAX_BODY {
+        return reply(&mut writer, 413, &json!({ "error": "request body too large" }).to_string());
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    // Browsers ask before sending JSON across origins.
+    if method == "OPTIONS" {
+        return reply(&mut writer, 204, "");
+    }
+    let body = match String::from_utf8(body) {
+        Ok(body) => body,
+        Err(_) => return reply(&mut writer, 400, &json!({ "error": "request body is not UTF-8" }).to_string()),
+    };
+    match Endpoint::of(method, path) {
+        Ok(endpoint) if endpoint.plays() => {}
+        answer => {
+            let (status, answer) = written(answer.and_then(|endpoint| codes.answer(endpoint, &body)));
+            return reply(&mut writer, status, &answer);
+        }
+    }
+    let (answer, answered) = channel();
+    if requests.send((method.to_string(), path.to_string(), body, answer)).is_err() {
+        return Ok(());
+    }
+    match answered.recv() {
+        Ok((status, answer)) => reply(&mut writer, status, &answer),
+        Err(_) => Ok(()),
+    }
+}
+
+/// A line of up to `MAX_LINE` bytes, or None if it's longer. At the
+/// end of the stream, the line is empty, as after the headers.
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.take(MAX_LINE as u64 + 1).read_line(&mut line)?;
+    Ok(if line.len() > MAX_LINE { None } else { Some(line) })
+}
+
+fn reply(writer: &mut TcpStream, status: u16, body: &str) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        414 => "URI Too Long",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        _ => "",
+    };
+    write!(writer,
+           "HTTP/1.1 {} {}\r\n\
+            Content-Type: application/json\r\n\
+            Content-Length: {}\r\n\
+            Access-Control-Allow-Origin: *\r\n\
+            Access-Control-Allow-Methods: POST, OPTIONS\r\n\
+            Access-Control-Allow-Headers: Content-Type\r\n\
+            Connection: close\r\n\r\n{}",
+           status,
+           reason,
+           body.len(),
+           body)?;
+    writer.flush()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use serde_json;
+    use serde_json::Value;
+
+    use gameplay::Rules;
+    use super::{Api, MAX_LINE, serve};
+
+    fn post(api: &mut Api, path: &str, body: &str) -> (u16, Value) {
+        let (status, answer) = api.handle("POST", path, body);
+        (status, serde_json::from_str(&answer).unwrap())
+    }
+
+    #[test]
+    fn history() {
+        let mut api = Api::new(Rules::default(), 12, 1);
+        let (status, hint) = post(&mut api, "/hint", r#"{"history": []}"#);
+        assert_eq!((status, hint["guess"].as_str()), (200, Some("1122")));
+        assert_eq!(hint["remaining"], 1296);
+
+        let history = r#"{"history": [["1122", "-"], ["3344", "BBBB"]]}"#;
+        let (_, consistent) = post(&mut api, "/consistent", history);
+        assert_eq!(consistent["codes"], json!(["3344"]));
+        let (_, hint) = post(&mut api, "/hint", history);
+        assert_eq!(hint["guess"], "3344");
+
+        let (status, refused) = post(&mut api, "/consistent", r#"{"history": [["1122", "-"], ["1111", "B"]]}"#);
+        assert_eq!(status, 422);
+        assert!(refused["error"].is_string());
+        assert_eq!(post(&mut api, "/hint", r#"{"history": [["1122", "BBBBB"]]}"#).0, 400);
+        assert_eq!(post(&mut api, "/hint", r#"{"history": "1122"}"#).0, 400);
+    }
+
+    #[test]
+    fn games() {
+        let mut api = Api::new(Rules::new(2, 2), 2, 1);
+        let (status, game) = post(&mut api, "/game", "");
+        assert_eq!(status, 200);
+        let path = format!("/game/{}/guess", game["id"].as_str().unwrap());
+
+        let answers: Vec<(u16, Value)> = ["11", "12", "21", "22"]
+            .iter()
+            .map(|guess| post(&mut api, &path, &format!(r#"{{"guess": "{}"}}"#, guess)))
+            .collect();
+        let over = answers.iter().position(|(_, a)| a["won"] == true || a["secret"].is_string()).unwrap();
+        assert!(over < 2);
+        assert!(answers[over + 1..].iter().all(|&(status, _)| status == 409));
+
+        assert_eq!(post(&mut api, "/game/2/guess", r#"{"guess": "11"}"#).0, 404);
+        assert_eq!(post(&mut api, &path.replace("/game/", "/game/0"), r#"{"guess": "11"}"#).0, 404);
+        assert_eq!(post(&mut api, "/game/x/guess", r#"{"guess": "11"}"#).0, 404);
+        assert_eq!(post(&mut api, "/nowhere", "{}").0, 404);
+        assert_eq!(api.handle("GET", "/score", "").0, 405);
+    }
+
+    #[test]
+    fn localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, Rules::default(), 12, 42));
+
+        let post = |path: &str, body: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            write!(stream,
+                   "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                   path,
+                   body.len(),
+                   body)
+                .unwrap();
+            let mut answer = String::new();
+            stream.read_to_string(&mut answer).unwrap();
+            answer
+        };
+        // Scored on the connection's thread; games on the games'.
+        let answer = post("/score", r#"{"secret": "1123", "guess": "3211"}"#);
+        assert!(answer.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(answer.ends_with(r#"{"response":"WWWW"}"#));
+        let answer = post("/game", "");
+        assert!(answer.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(answer.ends_with(r#""rows":12}"#));
+        assert!(post("/game/0/guess", r#"{"guess": "1122"}"#).starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        // A client that sends nothing doesn't keep others waiting.
+        let _idle = TcpStream::connect(address).unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET /{}", "x".repeat(MAX_LINE - 4)).unwrap();
+        let mut answer = String::new();
+        stream.read_to_string(&mut answer).unwrap();
+        assert!(answer.starts_with("HTTP/1.1 414 URI Too Long\r\n"));
+    }
+}
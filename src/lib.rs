@@ -1,5 +1,25 @@
 extern crate bit_set;
 extern crate bit_vec;
+extern crate rand;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "http")]
+#[macro_use]
+extern crate serde_json;
 
 pub mod gameplay;
 pub mod solver;
+pub mod record;
+pub mod adversary;
+pub mod consistency;
+pub mod lies;
+pub mod benchmark;
+pub mod static_mastermind;
+pub mod words;
+pub mod wordle;
+pub mod grand;
+pub mod render;
+pub mod protocol;
+pub mod server;
+#[cfg(feature = "http")]
+pub mod http;
@@ -0,0 +1,261 @@
+//! Mastermind with lies: a codebreaker that tolerates up to k
+//! erroneous responses.
+//!
+//! Rather than removing from S every code that would not give the
+//! same response, we count, for each code, how many responses it
+//! contradicts. A code remains a candidate until it contradicts
+//! more than k of them; the secret, contradicting only the lies,
+//! always remains.
+//!
+//! A candidate survives any response: unchanged if it agrees, with
+//! one lie fewer left if not, unless it has none left. Counting the
+//! survivors alone would not tell the first guesses apart, as every
+//! code survives them; instead, as Berlekamp does, we weigh each by
+//! the lies it has left, and take Knuth's minimax over that volume.
+//! A guess may be repeated: the codemaker may have lied about a win.
+//!
+//! That alone bounds no game's length; played out over all 1296
+//! codes against `liar`, games take at most 6 guesses with k = 1 and
+//! no lie, and at most 8 whichever turn the lie comes on; with k = 2,
+//! at most 10 with lies on turns 1 and 3, or 2 and 4.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::Pattern;
+//! use self::mastermind::lies::{TolerantSolver, liar};
+//!
+//! let code = Pattern::from_digits(['3', '6', '2', '5']);
+//! let breaker = TolerantSolver::new(liar(code, vec![2]), 1);
+//! assert_eq!(breaker.last(), Some(code));
+//! ```
+
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use gameplay::{KeyPegs, Pattern, Shield};
+use solver::{PatternSet, Solver};
+
+
+pub struct TolerantSolver {
+    codemaker: Shield,
+    lies: u8,
+    pub guessed: Vec<Pattern>,
+    /// How many responses each code contradicts, by lexical index.
+    errors: Vec<u8>,
+}
+
+impl TolerantSolver {
+    /// Assume at most `lies` of the codemaker's responses are wrong.
+    pub fn new(codemaker: Shield, lies: u8) -> TolerantSolver {
+        TolerantSolver {
+            codemaker,
+            lies,
+            guessed: vec![],
+            errors: vec![0; Pattern::cardinality() as usize],
+        }
+    }
+
+    /// Codes that contradict no more than k responses.
+    pub fn candidates(&self) -> PatternSet {
+        let mut s = PatternSet::all();
+        s.filter_with(&|p: &Pattern| self.errors(p) <= self.lies);
+        s
+    }
+
+    /// How many responses the code contradicts.
+    pub fn errors(&self, code: &Pattern) -> u8 {
+        self.errors[code.index() as usize]
+    }
+
+    /// Once only one candidate remains, the code is identified,
+    /// though we have yet to guess it.
+    pub fn identified(&self) -> Option<Pattern> {
+        let s = self.candidates();
+        if s.len() == 1 { s.iter().next() } else { None }
+    }
+
+    /// Return Some(guess) or None if we already won.
+    pub fn play(&mut self) -> Option<Pattern> {
+        if let Some(&prev) = self.guessed.last() {
+            let response = (self.codemaker)(&prev);
+            if response.win() {
+                return None;
+            }
+            self.count_errors(prev, response);
+        }
+
+        let guess = if self.guessed.is_empty() {
+            Solver::initial_guess()
+        } else {
+            self.next_guess()
+        };
+        self.guessed.push(guess);
+        Some(guess)
+    }
+
+    /// Charge each code that would not give the same response with
+    /// one more error.
+    pub fn count_errors(&mut self, guess: Pattern, response: KeyPegs) {
+        for code in Pattern::range() {
+            if guess.score(code) != response {
+                let e = &mut self.errors[code.index() as usize];
+                *e = e.saturating_add(1);
+            }
+        }
+    }
+
+    /// Minimax over all guesses, used or not, w.r.t. the volume of
+    /// the candidates that survive each response; among those with
+    /// the least worst case, prefer the candidate with the fewest
+    /// errors, then the least.
+    pub fn next_guess(&self) -> Pattern {
+        let s = self.candidates();
+        let lies_left: Vec<(Pattern, usize)> = s.iter()
+            .map(|c| (c, (self.lies - self.errors(&c)) as usize))
+            .collect();
+        let (_, mut next_guesses) = Pattern::range()
+            .fold((None, vec![]), |(least, guesses): (Option<Vec<usize>>, Vec<Pattern>), guess| {
+                let worst = self.worst_volume(guess, &lies_left);
+                match least {
+                    Some(ref least) if *least < worst => (Some(least.clone()), guesses),
+                    Some(ref least) if *least == worst => {
+                        let mut guesses = guesses;
+                        guesses.push(guess);
+                        (Some(worst), guesses)
+                    }
+                    _ => (Some(worst), vec![guess]),
+                }
+            });
+        next_guesses.sort_by_key(|g| (!s.contains(g), self.errors(g), *g));
+        next_guesses[0]
+    }
+
+    /// The largest volume left by any response to the guess. A code
+    /// survives a response it agrees with, keeping its lies, or one it
+    /// contradicts, spending one; its volume is the number of ways the
+    /// codemaker has left to lie about it. With many guesses to come,
+    /// one more lie left outweighs any number of codes with fewer, so
+    /// volumes are counts of the survivors by lies left, most first,
+    /// compared in that order.
+    fn worst_volume(&self, guess: Pattern, lies_left: &[(Pattern, usize)]) -> Vec<usize> {
+        let k = self.lies as usize;
+        let mut total = vec![0; k + 1];
+        let mut agreeing: HashMap<KeyPegs, Vec<usize>> = HashMap::new();
+        for &(code, left) in lies_left {
+            total[k - left] += 1;
+            agreeing.entry(guess.score(code)).or_insert_with(|| vec![0; k + 1])[k - left] += 1;
+        }
+        // Those that contradict the response had one more lie left.
+        agreeing.values()
+            .map(|agree| {
+                (0..=k).map(|i| agree[i] + if i > 0 { total[i - 1] - agree[i - 1] } else { 0 }).collect()
+            })
+            .max()
+            .expect("no worst volume: no candidates?")
+    }
+}
+
+
+impl Iterator for TolerantSolver {
+    type Item = Pattern;
+
+    fn next(&mut self) -> Option<Pattern> {
+        self.play()
+    }
+}
+
+
+/// A codemaker that lies on the given turns (counting from 1), and
+/// only those, by claiming no key pegs at all, or one white peg if
+/// the truth is no key pegs.
+pub fn liar(code: Pattern, lie_on: Vec<usize>) -> Shield {
+    let turn = Cell::new(0);
+    Box::new(move |guess: &Pattern| {
+        turn.set(turn.get() + 1);
+        let truth = code.score(*guess);
+        if !lie_on.contains(&turn.get()) {
+            truth
+        } else if truth == KeyPegs::new() {
+            KeyPegs::new().whites(1)
+        } else {
+            KeyPegs::new()
+        }
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{Pattern, shield};
+    use super::{TolerantSolver, liar};
+
+    /// Play every code against a liar, and return the total number
+    /// of guesses and the most any game took.
+    fn play_all(lies: u8, lie_on: Vec<usize>) -> (usize, usize) {
+        Pattern::range().fold((0, 0), |(total, worst), code| {
+            let guesses: Vec<Pattern> = TolerantSolver::new(liar(code, lie_on.clone()), lies).take(20).collect();
+            assert_eq!(guesses.last(), Some(&code));
+            (total + guesses.len(), worst.max(guesses.len()))
+        })
+    }
+
+    #[test]
+    fn no_lies() {
+        // With k = 0, errors amount to Knuth's filtering.
+        let code = Pattern::from_digits(['1', '1', '2', '3']);
+        let breaker = TolerantSolver::new(shield(code), 0);
+        let guesses: Vec<Pattern> = breaker.collect();
+        assert_eq!(guesses.last(), Some(&code));
+        assert!(guesses.len() <= 5);
+    }
+
+    #[test]
+    fn lie_about_a_win() {
+        let code = Pattern::from_digits(['1', '1', '2', '2']);
+        let mut breaker = TolerantSolver::new(liar(code, vec![1]), 1);
+        assert_eq!(breaker.play(), Some(code));
+        breaker.play();
+        assert_eq!(breaker.errors(&code), 1);
+        assert_eq!(breaker.last(), Some(code));
+    }
+
+    #[test]
+    fn lie_about_a_later_win() {
+        // Deny the win the first time the code is guessed, whenever
+        // that is: the guess is played again.
+        let code = Pattern::from_digits(['3', '6', '3', '2']);
+        let honest: Vec<Pattern> = TolerantSolver::new(liar(code, vec![]), 1).collect();
+        let turn = honest.len();
+        assert!(turn > 1);
+        let guesses: Vec<Pattern> = TolerantSolver::new(liar(code, vec![turn]), 1).collect();
+        assert_eq!(guesses[turn - 1], code);
+        assert_eq!(guesses.last(), Some(&code));
+        assert_eq!(guesses.iter().filter(|&&g| g == code).count(), 2);
+        assert!(guesses.len() <= 8);
+    }
+
+    #[test]
+    fn two_lies() {
+        let code = Pattern::from_digits(['6', '5', '4', '1']);
+        let mut breaker = TolerantSolver::new(liar(code, vec![1, 3]), 2);
+        let guesses: Vec<Pattern> = breaker.by_ref().take(20).collect();
+        assert_eq!(guesses.last(), Some(&code));
+        assert!(guesses.len() <= 10);
+        assert_eq!(breaker.errors(&code), 2);
+    }
+
+    #[test]
+    #[ignore]
+    fn one_lie_on_any_turn() {
+        // No game takes 7 guesses with no lie, so none is told later.
+        assert_eq!(play_all(1, vec![]), (6053, 6));
+        let worst: Vec<usize> = (1..7).map(|turn| play_all(1, vec![turn]).1).collect();
+        assert_eq!(worst, vec![8, 8, 8, 7, 7, 7]);
+    }
+
+    #[test]
+    #[ignore]
+    fn two_lies_on_alternate_turns() {
+        assert_eq!(play_all(2, vec![1, 3]), (10242, 10));
+        assert_eq!(play_all(2, vec![2, 4]), (9659, 10));
+    }
+}
@@ -1,39 +1,513 @@
-extern crate bit_set;
-extern crate bit_vec;
+extern crate mastermind;
 extern crate rand;
 
-use rand::distributions::{IndependentSample, Range};
+use std::env;
+use std::io;
+use std::io::{BufRead, IsTerminal, Write};
+use std::net::TcpListener;
+use std::process;
+use std::str::FromStr;
 
-pub mod gameplay;
-pub mod solver;
+use rand::Rng;
 
-use gameplay::{DecodingBoard, Pattern, shield};
-use solver::Solver;
+use mastermind::adversary::Evil;
+use mastermind::benchmark::benchmark_with;
+use mastermind::consistency::{conflict, is_consistent};
+use mastermind::gameplay::{Alphabet, DecodingBoard, GameRecord, KeyPegs, Pattern, Rules, seeded_rng, shield_with};
+#[cfg(feature = "http")]
+use mastermind::http;
+use mastermind::protocol::{Engine, Process, RefereeError, referee};
+use mastermind::record;
+use mastermind::server;
+use mastermind::render::{Palette, Renderer};
+use mastermind::solver::{Hint, PatternSet, Solver, Strategy};
+use mastermind::static_mastermind;
+use mastermind::words;
+
+
+const USAGE: &str = "usage: mmind [OPTIONS] [play [--evil] | solve | bench [--consistent] | static [GUESS...]
+             | words FILE [--any-guess] | replay FILE | engine | referee GAMES ENGINE... | serve [PORT]
+             | http [PORT]]
+
+options:
+  --seed N              reproduce a game
+  --pegs N              pegs per pattern (4)
+  --colors N            colors of code pegs (6)
+  --no-duplicates       no repeated colors in codes or guesses
+  --no-duplicate-codes  no repeated colors in codes
+  --blanks              holes may be left blank (_) in codes and guesses
+  --blank-codes         holes may be left blank (_) in codes
+  --black-pegs-only     no white pegs in feedback
+  --sum-hint            reveal the sum of the code's digits to the solver
+  --bulls-and-cows      4 pegs, 10 colors, no duplicates
+  --permutations N      codes are permutations of N colors; black pegs only
+  --color-letters       write colors as initials: R G B Y O P ...
+  --no-color            write colors as digits, not colored circles
+  --color-blind         colors for color blindness, labeled with their symbols
+
+http, the JSON API, is built with the http feature.";
+
+const DEFAULT_PORT: u16 = 4444;
+#[cfg(feature = "http")]
+const DEFAULT_HTTP_PORT: u16 = 8444;
+
+pub fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    // Each run reports its seed, so that any game can be reproduced.
+    let mut seed = None;
+    let (mut pegs, mut colors) = (Pattern::size() as u8, 6);
+    let mut no_duplicates = None;
+    let mut blanks = None;
+    let mut black_only = false;
+    let mut sum_hint = false;
+    let mut bulls_and_cows = false;
+    let mut permutations = None;
+    let mut color_letters = false;
+    let mut palette = Some(Palette::Classic);
+    while let Some(opt) = args.first().cloned().filter(|a| a.starts_with("--")) {
+        let mut value = || if args.len() > 1 { args.remove(1) } else { usage() };
+        match opt {
+            "--seed" => seed = Some(number(value())),
+            "--pegs" => pegs = number(value()),
+            "--colors" => colors = number(value()),
+            "--no-duplicates" => no_duplicates = Some(true),
+            "--no-duplicate-codes" => no_duplicates = Some(false),
+            "--blanks" => blanks = Some(true),
+            "--blank-codes" => blanks = Some(false),
+            "--black-pegs-only" => black_only = true,
+            "--sum-hint" => sum_hint = true,
+            "--bulls-and-cows" => bulls_and_cows = true,
+            "--permutations" => permutations = Some(number(value())),
+            "--color-letters" => color_letters = true,
+            "--no-color" => palette = None,
+            "--color-blind" => palette = palette.map(|_| Palette::ColorBlind),
+            _ => usage(),
+        }
+        args.remove(0);
+    }
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let rules = if bulls_and_cows {
+        Rules::bulls_and_cows()
+    } else if let Some(n) = permutations {
+        Rules::checked(n, n).map(|_| Rules::permutations(n)).unwrap_or_else(|| usage())
+    } else {
+        let rules = Rules::checked(pegs, colors).unwrap_or_else(|| usage());
+        let rules = match no_duplicates {
+            Some(_) if colors < pegs => usage(),
+            Some(in_guesses) => rules.no_duplicates(in_guesses),
+            None => rules,
+        };
+        match blanks {
+            Some(_) if Rules::checked(pegs, colors + 1).is_none() => usage(),
+            Some(in_guesses) => rules.with_blanks(in_guesses),
+            None => rules,
+        }
+    };
+    let rules = if black_only { rules.black_pegs_only() } else { rules };
+    let rules = match color_letters {
+        true if rules.colors as usize > Alphabet::colors().len() => usage(),
+        true => rules.with_alphabet(Alphabet::colors()),
+        false => rules,
+    };
+
+    // Colors only for a terminal, and not if NO_COLOR is set.
+    let renderer = match palette {
+        Some(palette) if io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none() => Renderer::ansi(palette),
+        _ => Renderer::plain(),
+    };
+
+    match args.as_slice() {
+        [] => demo(rules, seed, sum_hint, renderer),
+        ["play"] => play(rules, seed, false, renderer),
+        ["play", "--evil"] => play(rules, seed, true, renderer),
+        ["solve"] => solve(rules, renderer),
+        ["bench"] => bench(rules, Strategy::Minimax),
+        ["bench", "--consistent"] => bench(rules, Strategy::Consistent),
+        ["static", guesses @ ..] => static_guesses(rules, guesses),
+        ["words", path] => word_game(path, seed, false),
+        ["words", path, "--any-guess"] => word_game(path, seed, true),
+        ["replay", path] => replay(path),
+        ["engine"] => engine(),
+        ["referee", games, engines @ ..] if !engines.is_empty() => {
+            referee_games(Rules::new(rules.pegs, rules.colors), seed, games.parse().unwrap_or_else(|_| usage()), engines)
+        }
+        ["serve"] => serve_games(rules, seed, DEFAULT_PORT),
+        ["serve", port] => serve_games(rules, seed, port.parse().unwrap_or_else(|_| usage())),
+        #[cfg(feature = "http")]
+        ["http"] => serve_api(rules, seed, DEFAULT_HTTP_PORT),
+        #[cfg(feature = "http")]
+        ["http", port] => serve_api(rules, seed, port.parse().unwrap_or_else(|_| usage())),
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+/// An option's value, or usage if it doesn't parse or doesn't fit,
+/// e.g. `--pegs 260`.
+fn number<T: FromStr>(value: &str) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
 
 
 /// One player becomes the *codemaker*, the other the
 /// *codebreaker*. Guesses and feedback continue to alternate until
 /// either the codebreaker guesses correctly, or ten incorrect guesses
-/// are made.
-pub fn main() {
-    use rand::thread_rng;
+/// are made. As in Number Mastermind, the codemaker may also reveal
+/// the sum of the digits.
+fn demo(rules: Rules, seed: u64, sum_hint: bool, renderer: Renderer) {
+    let rng = &mut seeded_rng(seed);
 
-    let secret = {
-        let rng = &mut thread_rng();
-        let r = Range::new(0, Pattern::cardinality());
-        let x = r.ind_sample(rng);
-        Pattern::ith(x)
-    };
-    println!("codemaker: {}", secret);
+    println!("seed: {}", seed);
+    let secret = rules.random(rng);
+    println!("codemaker: {}", renderer.pattern(secret));
 
-    let maker = shield(secret);
+    let maker = shield_with(rules, secret);
 
-    let breaker = Solver::new(maker);
+    let breaker = Solver::with_rules(rules, maker);
+    let breaker = match secret.digit_sum() {
+        Some(sum) if sum_hint => {
+            println!("sum: {}", sum);
+            breaker.hint(Hint::DigitSum(sum))
+        }
+        _ => breaker,
+    };
 
     // TODO: support twelve (or ten, or eight) CLI arg
     let rows = DecodingBoard::default().rows as usize;
 
+    for (turn, g) in breaker.take(rows).enumerate() {
+        println!("turn {}:    {}  {}", turn + 1, renderer.pattern(g), renderer.key_pegs(rules.score(secret, g)));
+    }
+}
+
+
+/// As the demo, with a secret word from a dictionary; the solver
+/// guesses only words unless allowed any string of letters.
+fn word_game(path: &str, seed: u64, any_guess: bool) {
+    let codes = words::load(path, Alphabet::letters()).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    println!("seed: {}", seed);
+    let pick = seeded_rng(seed).gen_range(0, codes.len());
+    let secret = codes.iter().nth(pick).expect("pick is in range");
+    println!("codemaker: {}", secret);
+
+    let breaker = Solver::with_codes(codes.clone(), shield_with(codes.rules(), secret));
+    let breaker = if any_guess { breaker } else { breaker.guesses(codes) };
+    let rows = DecodingBoard::default().rows as usize;
     for (turn, g) in breaker.take(rows).enumerate() {
         println!("turn {}:    {}  {}", turn + 1, g, secret.score(g));
     }
 }
+
+
+/// The human is the codebreaker. On the hard difficulty, the
+/// codemaker is evil: it never commits to a secret.
+fn play(rules: Rules, seed: u64, evil: bool, renderer: Renderer) {
+    let secret = rules.random(&mut seeded_rng(seed));
+    let maker = Evil::with_rules(rules);
+    let respond = |guess: &Pattern| if evil { maker.respond(guess) } else { rules.score(secret, *guess) };
+
+    let rows = DecodingBoard::default().rows as usize;
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut turn = 1;
+
+    while turn <= rows {
+        print!("? ");
+        io::stdout().flush().expect("flush stdout");
+        let line = match lines.next() {
+            Some(line) => line.expect("read stdin"),
+            None => return,
+        };
+        let guess: Pattern = match rules.parse(line.trim()) {
+            Ok(guess) if rules.is_guess(&guess) => guess,
+            Ok(_) => {
+                println!("not allowed as a guess");
+                continue;
+            }
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+
+        let response: KeyPegs = respond(&guess);
+        println!("turn {}:    {}  {}", turn, renderer.pattern(guess), renderer.key_pegs(response));
+        if rules.is_win(&response) {
+            println!("codebreaker wins in {}", turn);
+            return;
+        }
+        turn += 1;
+    }
+
+    let secret = if evil { maker.reveal() } else { secret };
+    println!("codemaker wins: {}", renderer.pattern(secret));
+}
+
+
+/// The human is the codemaker, giving feedback on the solver's
+/// guesses; feedback that contradicts earlier feedback is refused,
+/// and any feedback may be taken back with undo, and given back with
+/// redo.
+fn solve(rules: Rules, renderer: Renderer) {
+    let mut breaker = Solver::observing(PatternSet::codes(rules));
+    let rows = DecodingBoard::default().rows as usize;
+    let stdin = io::stdin();
+
+    let mut history: Vec<(Pattern, KeyPegs)> = vec![];
+    let mut undone: Vec<(Pattern, KeyPegs)> = vec![];
+    let mut guess = breaker.next_guess();
+    println!("feedback: B and W pegs, - for none; undo or redo to take it back or give it again");
+    println!("turn 1:    {}", renderer.pattern(guess));
+
+    while history.len() < rows {
+        print!("feedback? ");
+        io::stdout().flush().expect("flush stdout");
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).expect("read stdin") == 0 {
+            return;
+        }
+        match line.trim() {
+            "undo" => match breaker.undo() {
+                Some(again) => {
+                    undone.push(history.pop().expect("as the solver observed"));
+                    guess = again;
+                    println!("turn {}:    {}", history.len() + 1, renderer.pattern(guess));
+                }
+                None => println!("nothing to undo"),
+            },
+            "redo" => match breaker.redo() {
+                Some(_) => {
+                    history.push(undone.pop().expect("as the solver undid"));
+                    guess = breaker.next_guess();
+                    println!("turn {}:    {}", history.len() + 1, renderer.pattern(guess));
+                }
+                None => println!("nothing to redo"),
+            },
+            line => {
+                let response: KeyPegs = match rules.parse_response(if line == "-" { "" } else { line }) {
+                    Ok(response) => response,
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                };
+
+                if !rules.is_response(guess, response) {
+                    println!("no code gives {} for {}; your feedback is impossible on its own", response, guess);
+                    continue;
+                }
+                history.push((guess, response));
+                if !is_consistent(rules, &history) {
+                    let turn = history.len();
+                    println!("your feedback on turn {} contradicts {}",
+                             turn,
+                             turns_phrase(&conflict(rules, &history, turn)));
+                    history.pop();
+                    continue;
+                }
+                undone.clear();
+                breaker.observe(guess, response);
+                if breaker.is_solved() {
+                    break;
+                }
+                if history.len() < rows {
+                    guess = breaker.next_guess();
+                    println!("turn {}:    {}", history.len() + 1, renderer.pattern(guess));
+                }
+            }
+        }
+    }
+    println!("codebreaker finished after {} turns", history.len());
+}
+
+/// "turn 1", "turns 1 and 2", "turns 1, 2 and 4"
+fn turns_phrase(turns: &[usize]) -> String {
+    let mut numbers: Vec<String> = turns.iter().map(|t| t.to_string()).collect();
+    match numbers.len() {
+        0 => "no turns".to_string(),
+        1 => format!("turn {}", numbers[0]),
+        _ => {
+            let last = numbers.pop().expect("at least two");
+            format!("turns {} and {}", numbers.join(", "), last)
+        }
+    }
+}
+
+
+/// Play against every code and summarize how many guesses it took.
+fn bench(rules: Rules, strategy: Strategy) {
+    let stats = benchmark_with(rules, strategy);
+    for (guesses, games) in &stats.histogram {
+        println!("{:2} guesses: {:5} games", guesses, games);
+    }
+    println!("total: {} guesses in {} games; average {:.3}; worst {}",
+             stats.total_guesses(),
+             stats.games(),
+             stats.average(),
+             stats.worst());
+}
+
+
+/// Answer the engine protocol on stdin and stdout.
+fn engine() {
+    let mut engine = Engine::new();
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        match engine.respond(&line.expect("read stdin")) {
+            Some(answer) => println!("{}", answer),
+            None => return,
+        }
+        io::stdout().flush().expect("flush stdout");
+    }
+}
+
+/// Play the same secrets against each engine, a command line such as
+/// "mmind engine", and compare; only pegs and colors carry over to
+/// the engines.
+fn referee_games(rules: Rules, seed: u64, games: usize, engines: &[&str]) {
+    println!("seed: {}", seed);
+    let rng = &mut seeded_rng(seed);
+    let secrets: Vec<Pattern> = (0..games).map(|_| rules.random(rng)).collect();
+    let rows = DecodingBoard::default().rows as usize;
+
+    for command in engines {
+        let mut engine = Process::spawn(command).unwrap_or_else(|e| {
+            eprintln!("{}: {}", command, e);
+            process::exit(1);
+        });
+        let (mut total, mut worst, mut unsolved) = (0, 0, 0);
+        for &secret in &secrets {
+            match referee(&mut engine, rules, secret, rows) {
+                Ok(guesses) => {
+                    total += guesses.len();
+                    worst = worst.max(guesses.len());
+                }
+                Err(RefereeError::Unsolved) => unsolved += 1,
+                Err(e) => {
+                    eprintln!("{}: {}: {}", command, secret, e);
+                    unsolved += 1;
+                }
+            }
+        }
+        let solved = games - unsolved;
+        println!("{}: {} guesses in {} games; average {:.3}; worst {}; unsolved {}",
+                 command,
+                 total,
+                 solved,
+                 if solved > 0 { total as f64 / solved as f64 } else { 0.0 },
+                 worst,
+                 unsolved);
+    }
+}
+
+
+/// Host games for clients on this machine, e.g. by `nc localhost 4444`.
+fn serve_games(rules: Rules, seed: u64, port: u16) {
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|e| {
+        eprintln!("port {}: {}", port, e);
+        process::exit(1);
+    });
+    println!("seed: {}", seed);
+    println!("listening on {}", listener.local_addr().expect("bound"));
+    let rows = DecodingBoard::default().rows as usize;
+    if let Err(e) = server::serve(listener, rules, rows, seed) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+
+/// Answer the JSON API on this machine, e.g. for a web frontend.
+#[cfg(feature = "http")]
+fn serve_api(rules: Rules, seed: u64, port: u16) {
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|e| {
+        eprintln!("port {}: {}", port, e);
+        process::exit(1);
+    });
+    println!("seed: {}", seed);
+    println!("listening on http://{}", listener.local_addr().expect("bound"));
+    let rows = DecodingBoard::default().rows as usize;
+    if let Err(e) = http::serve(listener, rules, rows, seed) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+
+/// Find guesses that identify every code without feedback along the
+/// way, or check the given ones.
+fn static_guesses(rules: Rules, guesses: &[&str]) {
+    if guesses.is_empty() {
+        let found = static_mastermind::search(rules);
+        let shown: Vec<String> = found.iter().map(|g| g.to_string()).collect();
+        println!("{} guesses: {}", found.len(), shown.join(" "));
+        return;
+    }
+
+    let guesses: Vec<Pattern> = guesses.iter()
+        .map(|g| rules.parse(g).unwrap_or_else(|e| {
+            eprintln!("{}: {}", g, e);
+            process::exit(2);
+        }))
+        .collect();
+    let groups = static_mastermind::ambiguities(rules, &guesses);
+    for codes in &groups {
+        let shown: Vec<String> = codes.iter().map(|c| c.to_string()).collect();
+        println!("ambiguous: {}", shown.join(" "));
+    }
+    if !groups.is_empty() {
+        process::exit(1);
+    }
+    println!("every code identified");
+}
+
+
+/// Re-render a recorded game, checking each response against the
+/// secret, if recorded.
+fn replay(path: &str) {
+    let game = record::load(path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    for (name, value) in &game.headers {
+        println!("{}: {}", name, value);
+    }
+    match game.secret {
+        Some(secret) => println!("codemaker: {}", secret),
+        None => println!("codemaker: ????"),
+    }
+    render(&game);
+
+    let wrong = record::verify(&game);
+    for d in &wrong {
+        eprintln!("turn {}: recorded {} but {} scores {}",
+                  d.turn,
+                  d.recorded,
+                  game.secret.expect("only games with secrets have discrepancies"),
+                  d.actual);
+    }
+    let rows = game.board().rows as usize;
+    if game.turns.len() > rows {
+        eprintln!("{} turns on a board of {} rows", game.turns.len(), rows);
+    }
+    if !wrong.is_empty() || game.turns.len() > rows {
+        process::exit(1);
+    }
+}
+
+fn render(game: &GameRecord) {
+    for (turn, &(g, response)) in game.turns.iter().enumerate() {
+        println!("turn {}:    {}  {}", turn + 1, g, response);
+    }
+}
@@ -0,0 +1,273 @@
+//! A line protocol for codebreakers, as UCI is for chess engines, so
+//! that codebreakers, ours or anyone's, can be pitted against each
+//! other.
+//!
+//! A referee, as codemaker, sends commands one per line, and the
+//! engine answers each with one line:
+//!
+//!  - `newgame 4 6`: start a game of 4 pegs and 6 colors; `ok`, or
+//!    `error` if there are more codes than `MAX_CODES`.
+//!  - `bestguess`: `bestguess 1122`, the engine's choice of guess.
+//!  - `guess 1122`: 1122 is guessed, whoever chose it; `ok`.
+//!  - `feedback BW`: the response to the last guess, `-` if no key
+//!    pegs; `ok`.
+//!  - `quit`: no answer; the engine exits.
+//!
+//! Patterns are written in digits, key pegs as by `KeyPegs`. Anything
+//! amiss is answered with `error` and a reason.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::Rules;
+//! use self::mastermind::protocol::{Connection, Engine, referee};
+//!
+//! let mut engine = Engine::new();
+//! assert_eq!(engine.ask("newgame 4 6").unwrap(), "ok");
+//! assert_eq!(engine.ask("bestguess").unwrap(), "bestguess 1122");
+//! assert_eq!(engine.ask("feedback W").unwrap(), "ok");
+//! assert_eq!(engine.ask("guess 1234").unwrap(), "ok");
+//! assert!(engine.ask("feedback BBBBB").unwrap().starts_with("error"));
+//! assert!(engine.ask("newgame 9 10").unwrap().starts_with("error"));
+//!
+//! let rules = Rules::default();
+//! let guesses = referee(&mut engine, rules, rules.parse("3632").unwrap(), 10).unwrap();
+//! assert_eq!(guesses.len(), 5);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use gameplay::{Pattern, Rules};
+use solver::{PatternSet, Solver};
+
+
+/// The most codes the engine will play with: each guess is chosen by
+/// minimax over all the codes, which for 10^9 of them, as with
+/// `newgame 9 10`, would never finish.
+pub const MAX_CODES: u32 = 1 << 16;
+
+
+/// Our codebreaker, answering the protocol.
+#[derive(Default)]
+pub struct Engine {
+    game: Option<(Solver, Option<Pattern>)>,
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::default()
+    }
+
+    /// The answer to a command; None for `quit`.
+    pub fn respond(&mut self, line: &str) -> Option<String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let answer = match words.as_slice() {
+            ["quit"] => return None,
+            ["newgame", pegs, colors] => self.new_game(pegs, colors),
+            ["newgame"] => self.new_game("4", "6"),
+            [command, ..] if self.game.is_none() && ["bestguess", "guess", "feedback"].contains(command) => {
+                Err("no game; newgame first".to_string())
+            }
+            ["bestguess"] => self.best_guess().map(|guess| format!("bestguess {}", guess)),
+            ["guess", guess] => self.guess(guess),
+            ["feedback", response] => self.feedback(response),
+            [] => Err("no command".to_string()),
+            _ => Err(format!("unknown command: {}", line.trim())),
+        };
+        Some(answer.unwrap_or_else(|reason| format!("error {}", reason)))
+    }
+
+    fn new_game(&mut self, pegs: &str, colors: &str) -> Result<String, String> {
+        let rules = match (pegs.parse(), colors.parse()) {
+            (Ok(pegs), Ok(colors)) => Rules::checked(pegs, colors),
+            _ => None,
+        };
+        let rules = rules.ok_or_else(|| "too many or too few pegs or colors".to_string())?;
+        if rules.cardinality() > MAX_CODES {
+            return Err(format!("too many codes; at most {}", MAX_CODES));
+        }
+        self.game = Some((Solver::observing(PatternSet::codes(rules)), None));
+        Ok("ok".to_string())
+    }
+
+    fn best_guess(&mut self) -> Result<Pattern, String> {
+        let (ref mut breaker, ref mut last) = *self.game.as_mut().expect("a game");
+        if breaker.is_solved() {
+            return Err("solved already".to_string());
+        }
+        if breaker.s.is_empty() {
+            return Err("no code fits the feedback".to_string());
+        }
+        let guess = breaker.next_guess();
+        *last = Some(guess);
+        Ok(guess)
+    }
+
+    fn guess(&mut self, guess: &str) -> Result<String, String> {
+        let (ref breaker, ref mut last) = *self.game.as_mut().expect("a game");
+        let rules = breaker.s.rules();
+        *last = Some(rules.parse(guess).map_err(|e| e.to_string())?);
+        Ok("ok".to_string())
+    }
+
+    fn feedback(&mut self, response: &str) -> Result<String, String> {
+        let (ref mut breaker, ref mut last) = *self.game.as_mut().expect("a game");
+        let rules = breaker.s.rules();
+        let guess = last.ok_or_else(|| "no guess to respond to".to_string())?;
+        let response = rules.parse_response(if response == "-" { "" } else { response }).map_err(|e| e.to_string())?;
+        breaker.observe(guess, response);
+        *last = None;
+        Ok("ok".to_string())
+    }
+}
+
+
+/// A codebreaker that speaks the protocol.
+pub trait Connection {
+    /// Send a command and wait for the answer.
+    fn ask(&mut self, command: &str) -> io::Result<String>;
+}
+
+impl Connection for Engine {
+    fn ask(&mut self, command: &str) -> io::Result<String> {
+        Ok(self.respond(command).unwrap_or_default())
+    }
+}
+
+
+/// An engine in another process, speaking on its stdin and stdout.
+pub struct Process {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl Process {
+    /// Run a command line such as `mmind engine`; words are split at
+    /// whitespace, without quoting.
+    pub fn spawn(command_line: &str) -> io::Result<Process> {
+        let words: Vec<&str> = command_line.split_whitespace().collect();
+        let (program, args) = words.split_first().ok_or_else(|| io::Error::other("no command"))?;
+        let mut child = Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let input = child.stdin.take().expect("piped stdin");
+        let output = BufReader::new(child.stdout.take().expect("piped stdout"));
+        Ok(Process { child, input, output })
+    }
+}
+
+impl Connection for Process {
+    fn ask(&mut self, command: &str) -> io::Result<String> {
+        writeln!(self.input, "{}", command)?;
+        self.input.flush()?;
+        let mut line = String::new();
+        if self.output.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "engine exited"));
+        }
+        Ok(line.trim().to_string())
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = writeln!(self.input, "quit").and_then(|_| self.input.flush());
+        let _ = self.child.wait();
+    }
+}
+
+
+/// Play one game as codemaker against an engine, allowing it as many
+/// guesses as there are rows; its guesses, the last one winning.
+pub fn referee<C: Connection + ?Sized>(engine: &mut C,
+                                       rules: Rules,
+                                       secret: Pattern,
+                                       rows: usize)
+                                       -> Result<Vec<Pattern>, RefereeError> {
+    let mut ask = |command: String| -> Result<String, RefereeError> {
+        let answer = engine.ask(&command).map_err(|e| RefereeError::Io(e.to_string()))?;
+        if answer.starts_with("error") {
+            return Err(RefereeError::Answer(command, answer));
+        }
+        Ok(answer)
+    };
+
+    ask(format!("newgame {} {}", rules.pegs, rules.colors))?;
+    let mut guesses = vec![];
+    while guesses.len() < rows {
+        let answer = ask("bestguess".to_string())?;
+        let guess = match answer.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["bestguess", guess] => rules.parse(guess).ok().filter(|g| rules.is_guess(g)),
+            _ => None,
+        };
+        let guess = guess.ok_or_else(|| RefereeError::Answer("bestguess".to_string(), answer.clone()))?;
+        guesses.push(guess);
+
+        let response = rules.score(secret, guess);
+        if rules.is_win(&response) {
+            return Ok(guesses);
+        }
+        let written = response.to_string();
+        ask(format!("feedback {}", if written.is_empty() { "-" } else { &written }))?;
+    }
+    Err(RefereeError::Unsolved)
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RefereeError {
+    Io(String),
+    /// An error or nonsense in answer to a command.
+    Answer(String, String),
+    /// Out of rows.
+    Unsolved,
+}
+
+impl Display for RefereeError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match *self {
+            RefereeError::Io(ref msg) => fmt.write_str(msg),
+            RefereeError::Answer(ref command, ref answer) => write!(fmt, "{:?} answered {:?}", command, answer),
+            RefereeError::Unsolved => fmt.write_str("out of rows"),
+        }
+    }
+}
+
+impl Error for RefereeError {}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{Rules, shield_with};
+    use solver::Solver;
+    use super::{Connection, Engine, RefereeError, referee};
+
+    #[test]
+    fn engine_as_solver() {
+        let rules = Rules::new(3, 5);
+        let mut engine = Engine::new();
+        for code in rules.codes() {
+            let guesses = referee(&mut engine, rules, code, 12).unwrap();
+            assert_eq!(guesses, Solver::with_rules(rules, shield_with(rules, code)).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut engine = Engine::new();
+        assert!(engine.ask("bestguess").unwrap().starts_with("error"));
+        assert!(engine.ask("newgame 40 6").unwrap().starts_with("error"));
+        assert_eq!(engine.ask("newgame 2 2").unwrap(), "ok");
+        assert!(engine.ask("feedback B").unwrap().starts_with("error"));
+        assert!(engine.ask("frobnicate").unwrap().starts_with("error"));
+        assert_eq!(engine.respond("quit"), None);
+
+        // No code fits the feedback.
+        let rules = Rules::new(2, 2);
+        assert_eq!(engine.ask("guess 11").unwrap(), "ok");
+        assert_eq!(engine.ask("feedback W").unwrap(), "ok");
+        assert!(engine.ask("bestguess").unwrap().starts_with("error"));
+        assert_eq!(referee(&mut engine, rules, rules.parse("21").unwrap(), 1), Err(RefereeError::Unsolved));
+    }
+}
@@ -0,0 +1,396 @@
+//! Game records in a plain-text notation, after chess's PGN.
+//!
+//! A record starts with headers giving metadata and the game
+//! configuration, followed by one line per turn with the guess and
+//! the feedback it got; `-` stands for no key pegs at all. The
+//! secret is optional: a codebreaker may record a game before
+//! knowing it.
+//!
+//! The configuration headers give the rules, and are left out where
+//! they're the usual ones:
+//!
+//! - `Pegs` and `Colors`, e.g. `[Pegs "5"]` and `[Colors "8"]`;
+//! - `Duplicates`: `yes`, `no`, or `guesses`, in guesses only;
+//! - `Blanks`: `no`, `yes`, or `codes`, in codes only;
+//! - `Feedback`: `black and white` or `black only`;
+//! - `Alphabet`, the symbols patterns are written in.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::{GameRecord, Pattern, Rules};
+//!
+//! let text = "[Codemaker \"Alice\"]
+//! [Codebreaker \"Bob\"]
+//! [Date \"2015-05-20\"]
+//! [Secret \"1123\"]
+//!
+//! 1122 BBB
+//! 1344 BW
+//! 1123 BBBB
+//! ";
+//! let game: GameRecord = text.parse().unwrap();
+//! assert_eq!(game.secret, Some("1123".parse().unwrap()));
+//! assert_eq!(game.header("Codebreaker"), Some("Bob"));
+//! assert_eq!(game.turns.len(), 3);
+//! assert!(game.won());
+//! assert_eq!(format!("{}", game), text);
+//!
+//! let game: GameRecord = "[Pegs \"5\"]\n[Colors \"8\"]\n\n12345 BBBBB\n".parse().unwrap();
+//! assert_eq!(game.rules, Rules::new(5, 8));
+//! assert!(game.won());
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use gameplay::{Alphabet, DecodingBoard, Feedback, GameRecord, KeyPegs, ParseError, Rules};
+
+
+/// Headers giving the configuration rather than metadata.
+const SECRET: &str = "Secret";
+const PEGS: &str = "Pegs";
+const COLORS: &str = "Colors";
+const DUPLICATES: &str = "Duplicates";
+const BLANKS: &str = "Blanks";
+const FEEDBACK: &str = "Feedback";
+const ALPHABET: &str = "Alphabet";
+const ROWS: &str = "Rows";
+
+
+impl GameRecord {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|h| h.0 == name).map(|h| h.1.as_str())
+    }
+
+    /// Set a header, replacing any previous value.
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        match self.headers.iter_mut().find(|h| h.0 == name) {
+            Some(h) => h.1 = value.to_string(),
+            None => self.headers.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The decoding board has twelve rows unless the `Rows` header
+    /// says otherwise.
+    pub fn board(&self) -> DecodingBoard {
+        match self.header(ROWS).and_then(|r| r.parse().ok()) {
+            Some(rows) => DecodingBoard { rows },
+            None => DecodingBoard::default(),
+        }
+    }
+}
+
+
+impl Display for GameRecord {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        for (name, value) in &self.headers {
+            writeln!(fmt, "[{} \"{}\"]", name, value)?;
+        }
+        for (name, value) in config(self.rules) {
+            writeln!(fmt, "[{} \"{}\"]", name, value)?;
+        }
+        if let Some(secret) = self.secret {
+            writeln!(fmt, "[{} \"{}\"]", SECRET, secret)?;
+        }
+        writeln!(fmt)?;
+        for &(guess, response) in &self.turns {
+            if response == KeyPegs::new() {
+                writeln!(fmt, "{} -", guess)?;
+            } else {
+                writeln!(fmt, "{} {}", guess, response)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(text: &str) -> Result<GameRecord, RecordError> {
+        let mut game = GameRecord::new(None);
+        // The secret and the configuration, with their lines, until
+        // the headers are done and the rules known.
+        let mut secret = None;
+        let mut config = vec![];
+        let mut headers_done = false;
+
+        for (ix, line) in text.lines().enumerate() {
+            let err = |kind| RecordError { line: ix + 1, kind };
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            } else if line.starts_with('[') {
+                if headers_done {
+                    return Err(err(ErrorKind::HeaderAfterTurns));
+                }
+                let (name, value) = parse_header(line).ok_or_else(|| err(ErrorKind::Header))?;
+                match name {
+                    SECRET => secret = Some((ix + 1, value)),
+                    PEGS | COLORS | DUPLICATES | BLANKS | FEEDBACK | ALPHABET => config.push((ix + 1, name, value)),
+                    ROWS if value.parse::<u8>().is_err() => return Err(err(ErrorKind::Header)),
+                    _ => game.set_header(name, value),
+                }
+            } else {
+                if !headers_done {
+                    end_headers(&mut game, &config, secret)?;
+                    headers_done = true;
+                }
+                let mut fields = line.split_whitespace();
+                let guess = fields.next().expect("line is not empty");
+                let response = fields.next().ok_or_else(|| err(ErrorKind::Turn))?;
+                if fields.next().is_some() {
+                    return Err(err(ErrorKind::Turn));
+                }
+                let response = if response == "-" { "" } else { response };
+                game.push(game.rules.parse(guess).map_err(|e| err(ErrorKind::Peg(e)))?,
+                          game.rules.parse_response(response).map_err(|e| err(ErrorKind::Peg(e)))?);
+            }
+        }
+
+        if !headers_done {
+            end_headers(&mut game, &config, secret)?;
+        }
+        Ok(game)
+    }
+}
+
+/// Settle the rules from the configuration headers, then read the
+/// secret by them.
+fn end_headers(game: &mut GameRecord, config: &[(usize, &str, &str)],
+               secret: Option<(usize, &str)>) -> Result<(), RecordError> {
+    let unsupported = |line: usize, name: &str| RecordError { line, kind: ErrorKind::Unsupported(name.to_string()) };
+    let header = |name| config.iter().rev().find(|&&(_, n, _)| n == name).map(|&(line, _, value)| (line, value));
+    let number = |name, default| match header(name) {
+        Some((line, value)) => value.parse().map(|n| (line, n)).map_err(|_| unsupported(line, name)),
+        None => Ok((0, default)),
+    };
+
+    let usual = Rules::default();
+    let (pegs_line, pegs) = number(PEGS, usual.pegs)?;
+    let (colors_line, colors) = number(COLORS, usual.colors)?;
+    let mut rules = Rules::checked(pegs, colors).ok_or_else(|| {
+        if colors_line > pegs_line { unsupported(colors_line, COLORS) } else { unsupported(pegs_line, PEGS) }
+    })?;
+    if let Some((line, value)) = header(DUPLICATES) {
+        rules = match value {
+            "yes" => rules,
+            "no" if colors >= pegs => rules.no_duplicates(true),
+            "guesses" if colors >= pegs => rules.no_duplicates(false),
+            _ => return Err(unsupported(line, DUPLICATES)),
+        };
+    }
+    if let Some((line, value)) = header(BLANKS) {
+        let fits = colors.checked_add(1).and_then(|symbols| Rules::checked(pegs, symbols)).is_some();
+        rules = match value {
+            "no" => rules,
+            "yes" if fits => rules.with_blanks(true),
+            "codes" if fits => rules.with_blanks(false),
+            _ => return Err(unsupported(line, BLANKS)),
+        };
+    }
+    if let Some((line, value)) = header(FEEDBACK) {
+        rules = match value {
+            "black and white" => rules,
+            "black only" => rules.black_pegs_only(),
+            _ => return Err(unsupported(line, FEEDBACK)),
+        };
+    }
+    if let Some((line, value)) = header(ALPHABET) {
+        rules = match Alphabet::new(value) {
+            Some(alphabet) if alphabet.len() >= colors as usize => rules.with_alphabet(alphabet),
+            _ => return Err(unsupported(line, ALPHABET)),
+        };
+    }
+    game.rules = rules;
+
+    if let Some((line, value)) = secret {
+        let code = rules.parse(value).map_err(|e| RecordError { line, kind: ErrorKind::Peg(e) })?;
+        game.secret = Some(code);
+    }
+    Ok(())
+}
+
+/// The configuration headers for the rules, other than the usual.
+fn config(rules: Rules) -> Vec<(&'static str, String)> {
+    let mut headers = vec![];
+    if rules == Rules::default() {
+        return headers;
+    }
+    headers.push((PEGS, rules.pegs.to_string()));
+    headers.push((COLORS, rules.colors.to_string()));
+    match (rules.duplicates, rules.duplicate_guesses) {
+        (true, _) => {}
+        (false, true) => headers.push((DUPLICATES, "guesses".to_string())),
+        (false, false) => headers.push((DUPLICATES, "no".to_string())),
+    }
+    match (rules.blanks, rules.blank_guesses) {
+        (false, _) => {}
+        (true, true) => headers.push((BLANKS, "yes".to_string())),
+        (true, false) => headers.push((BLANKS, "codes".to_string())),
+    }
+    if rules.feedback == Feedback::BlackOnly {
+        headers.push((FEEDBACK, "black only".to_string()));
+    }
+    if rules.alphabet != Alphabet::default() {
+        headers.push((ALPHABET, rules.alphabet.as_str().to_string()));
+    }
+    headers
+}
+
+/// Split `[Name "value"]` into its parts.
+fn parse_header(line: &str) -> Option<(&str, &str)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, quoted) = inner.split_at(inner.find(' ')?);
+    let value = quoted.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name, value))
+}
+
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<GameRecord, RecordError> {
+    let mut text = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut text))
+        .map_err(|e| RecordError { line: 0, kind: ErrorKind::Io(e.to_string()) })?;
+    text.parse()
+}
+
+pub fn save<P: AsRef<Path>>(path: P, game: &GameRecord) -> io::Result<()> {
+    let mut f = File::create(path)?;
+    write!(f, "{}", game)
+}
+
+
+/// A turn whose recorded feedback doesn't match the secret.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub struct Discrepancy {
+    /// Turns count from 1, as on the board.
+    pub turn: usize,
+    pub recorded: KeyPegs,
+    pub actual: KeyPegs,
+}
+
+/// Check each response against the secret, scored by the game's rules.
+/// Without a secret, there's nothing to check.
+///
+/// ```rust
+/// use self::mastermind::gameplay::GameRecord;
+/// use self::mastermind::record::verify;
+///
+/// let game: GameRecord = "[Secret \"1123\"]\n1122 BBB\n1344 BB\n".parse().unwrap();
+/// let wrong = verify(&game);
+/// assert_eq!(wrong.len(), 1);
+/// assert_eq!((wrong[0].turn, format!("{}", wrong[0].actual)), (2, "BW".to_string()));
+/// ```
+pub fn verify(game: &GameRecord) -> Vec<Discrepancy> {
+    let secret = match game.secret {
+        Some(secret) => secret,
+        None => return vec![],
+    };
+
+    game.turns
+        .iter()
+        .enumerate()
+        .map(|(ix, &(guess, recorded))| {
+            Discrepancy {
+                turn: ix + 1,
+                recorded,
+                actual: game.rules.score(secret, guess),
+            }
+        })
+        .filter(|d| d.recorded != d.actual)
+        .collect()
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RecordError {
+    /// Lines count from 1; 0 means the record couldn't be read at all.
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ErrorKind {
+    Io(String),
+    /// Not of the form `[Name "value"]`.
+    Header,
+    HeaderAfterTurns,
+    /// Not of the form `guess response`.
+    Turn,
+    Peg(ParseError),
+    /// A configuration no rules allow, e.g. 20 pegs.
+    Unsupported(String),
+}
+
+impl Display for RecordError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Io(ref msg) => return fmt.write_str(msg),
+            _ => write!(fmt, "line {}: ", self.line)?,
+        }
+        match self.kind {
+            ErrorKind::Io(_) => Ok(()),
+            ErrorKind::Header => fmt.write_str("expected [Name \"value\"]"),
+            ErrorKind::HeaderAfterTurns => fmt.write_str("headers must precede turns"),
+            ErrorKind::Turn => fmt.write_str("expected guess and response, e.g. 1122 BW"),
+            ErrorKind::Peg(ref e) => write!(fmt, "{}", e),
+            ErrorKind::Unsupported(ref name) => write!(fmt, "unsupported {}", name),
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{GameRecord, Pattern, Rules};
+    use gameplay::ParseError;
+    use super::{ErrorKind, RecordError, verify};
+
+    #[test]
+    fn no_pegs() {
+        let game: GameRecord = "3456 -\n".parse().unwrap();
+        assert_eq!(game.secret, None);
+        let p: Pattern = "3456".parse().unwrap();
+        assert_eq!(game.turns, vec![(p, "".parse().unwrap())]);
+        assert_eq!(format!("{}", game), "\n3456 -\n");
+    }
+
+    #[test]
+    fn errors() {
+        let bad = |text: &str| text.parse::<GameRecord>().unwrap_err();
+        assert_eq!(bad("1122 BW\n[Secret \"1123\"]"),
+                   RecordError { line: 2, kind: ErrorKind::HeaderAfterTurns });
+        assert_eq!(bad("[Colors \"40\"]"),
+                   RecordError { line: 1, kind: ErrorKind::Unsupported("Colors".to_string()) });
+        assert_eq!(bad("[Pegs \"3\"]\n[Duplicates \"no\"]\n[Colors \"2\"]"),
+                   RecordError { line: 2, kind: ErrorKind::Unsupported("Duplicates".to_string()) });
+        assert_eq!(bad("[Secret \"1123\"]\n[Pegs \"5\"]").kind, ErrorKind::Peg(ParseError::Length(4)));
+        assert_eq!(bad("\n1122"), RecordError { line: 2, kind: ErrorKind::Turn });
+        assert_eq!(format!("{}", bad("[Date 2015]")), "line 1: expected [Name \"value\"]");
+    }
+
+    #[test]
+    fn rules() {
+        let rules = Rules::bulls_and_cows().with_blanks(false);
+        let secret = rules.parse("7_9_").unwrap();
+        let mut game = GameRecord::with_rules(rules, Some(secret));
+        game.push(rules.parse("7890").unwrap(), "BB".parse().unwrap());
+        game.push(secret, "BBBB".parse().unwrap());
+        let text = format!("{}", game);
+        assert_eq!(text, "[Pegs \"4\"]\n[Colors \"10\"]\n[Duplicates \"no\"]\n[Blanks \"codes\"]\n\
+                          [Secret \"7_9_\"]\n\n7890 BB\n7_9_ BBBB\n");
+        assert_eq!(text.parse::<GameRecord>().unwrap(), game);
+        assert!(game.won());
+        assert!(verify(&game).is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! Rendering patterns and key pegs for a terminal.
+//!
+//! With a palette, each color is drawn as a circle in its ANSI color,
+//! and key pegs as dots: black ● and white ○. The color-blind palette
+//! uses colors that stay distinct for most kinds of color blindness,
+//! and labels each circle with its symbol too, as the pattern would
+//! be typed, e.g. `1` or, with color initials, `R`. Without a palette,
+//! patterns and key pegs are written as by `Display`.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::{Alphabet, KeyPegs, Pattern, Rules};
+//! use self::mastermind::render::{Palette, Renderer};
+//!
+//! let p: Pattern = "1123".parse().unwrap();
+//! let k = KeyPegs::new().blacks(1).whites(2);
+//! assert_eq!(Renderer::plain().pattern(p), "1123");
+//! assert_eq!(Renderer::plain().key_pegs(k), "BWW");
+//!
+//! let classic = Renderer::ansi(Palette::Classic);
+//! assert_eq!(classic.pattern(p).matches('●').count(), 4);
+//! assert!(classic.pattern(p).starts_with("\x1b[38;5;196m●"));
+//! assert_eq!(classic.key_pegs(k).matches('○').count(), 2);
+//!
+//! let color_blind = Renderer::ansi(Palette::ColorBlind);
+//! assert!(color_blind.pattern(p).contains("●1"));
+//! let initials = Rules::default().with_alphabet(Alphabet::colors());
+//! assert!(color_blind.pattern(initials.parse("1123").unwrap()).contains("●R"));
+//! ```
+
+use gameplay::{BLANK, KeyPegs, Pattern, Rules};
+
+
+/// A color of code peg: its name, and its number among the 256 ANSI
+/// colors.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Color {
+    pub name: &'static str,
+    pub ansi: u8,
+}
+
+const CLASSIC: &[Color] = &[
+    Color { name: "red", ansi: 196 },
+    Color { name: "green", ansi: 46 },
+    Color { name: "blue", ansi: 21 },
+    Color { name: "yellow", ansi: 226 },
+    Color { name: "orange", ansi: 208 },
+    Color { name: "purple", ansi: 129 },
+    Color { name: "cyan", ansi: 51 },
+    Color { name: "white", ansi: 255 },
+    Color { name: "pink", ansi: 213 },
+    Color { name: "brown", ansi: 130 },
+];
+
+/// After Okabe and Ito's palette.
+const COLOR_BLIND: &[Color] = &[
+    Color { name: "orange", ansi: 214 },
+    Color { name: "sky blue", ansi: 117 },
+    Color { name: "bluish green", ansi: 36 },
+    Color { name: "yellow", ansi: 227 },
+    Color { name: "blue", ansi: 25 },
+    Color { name: "vermillion", ansi: 202 },
+    Color { name: "reddish purple", ansi: 175 },
+    Color { name: "grey", ansi: 248 },
+];
+
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Palette {
+    Classic,
+    ColorBlind,
+}
+
+impl Palette {
+    /// The colors, by color index; colors beyond these are drawn as
+    /// their symbols.
+    pub fn colors(&self) -> &'static [Color] {
+        match *self {
+            Palette::Classic => CLASSIC,
+            Palette::ColorBlind => COLOR_BLIND,
+        }
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Renderer {
+    palette: Option<Palette>,
+}
+
+impl Renderer {
+    /// No color: as `Display` writes them.
+    pub fn plain() -> Renderer {
+        Renderer { palette: None }
+    }
+
+    pub fn ansi(palette: Palette) -> Renderer {
+        Renderer { palette: Some(palette) }
+    }
+
+    pub fn pattern(&self, p: Pattern) -> String {
+        if self.palette.is_none() {
+            return p.to_string();
+        }
+        p.code_pegs()
+            .iter()
+            .zip(p.to_string().chars())
+            .map(|(&c, symbol)| self.draw(c, symbol, p.is_blank(c)))
+            .collect()
+    }
+
+    /// One code peg of the given color, as in a pattern of the rules.
+    pub fn code_peg(&self, rules: Rules, color: u8) -> String {
+        let blank = color == rules.colors;
+        self.draw(color, if blank { BLANK } else { rules.alphabet.symbol(color) }, blank)
+    }
+
+    fn draw(&self, color: u8, symbol: char, blank: bool) -> String {
+        let palette = match self.palette {
+            Some(palette) => palette,
+            None => return symbol.to_string(),
+        };
+        match palette.colors().get(color as usize) {
+            _ if blank => "○".to_string(),
+            Some(c) if palette == Palette::ColorBlind => format!("\x1b[38;5;{}m●{}\x1b[0m", c.ansi, symbol),
+            Some(c) => format!("\x1b[38;5;{}m●\x1b[0m", c.ansi),
+            None => symbol.to_string(),
+        }
+    }
+
+    pub fn key_pegs(&self, k: KeyPegs) -> String {
+        if self.palette.is_none() {
+            return k.to_string();
+        }
+        let blacks: String = (0..k.black_count()).map(|_| '●').collect();
+        let whites: String = (0..k.white_count()).map(|_| '○').collect();
+        format!("{}\x1b[97m{}\x1b[0m", blacks, whites)
+    }
+}
@@ -0,0 +1,485 @@
+//! A game server: any number of games at once, each with its own
+//! secret behind a shield, played a line at a time over TCP.
+//!
+//! A client sends commands one per line, and gets one line back:
+//!
+//!  - `new alice`: start a game for a player; `game 5f0c62e1a4d3b7e9`,
+//!    its token, which only the player knows.
+//!  - `join 5f0c62e1a4d3b7e9`: play that game, e.g. after reconnecting;
+//!    `game 5f0c62e1a4d3b7e9`.
+//!  - `guess 1122`: the key pegs, e.g. `BW`, `-` for none; when the
+//!    game is over, then `win 4`, in so many turns, or, out of rows,
+//!    `lose 3632` and the code.
+//!  - `scores`: the winners, fewest turns first, e.g.
+//!    `scores bob:3 alice:5`.
+//!  - `quit`: `bye`, and the connection closes.
+//!
+//! Anything amiss is answered with `error` and a reason. Lines longer
+//! than `MAX_LINE` are answered so, and the connection closes, as it
+//! does for a client silent for an hour, or beyond `MAX_CLIENTS`.
+//!
+//! A game is kept a while once it's over, or once its player stops
+//! guessing, and then dropped; the scores stay.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::Rules;
+//! use self::mastermind::server::Server;
+//!
+//! let mut server = Server::new(Rules::default(), 12, 7);
+//! let mut session = None;
+//! let game = server.respond(&mut session, "new alice");
+//! assert_eq!(game, format!("game {}", session.unwrap()));
+//! assert!(server.respond(&mut session, "guess 1122").chars().all(|c| "BW-".contains(c)));
+//! assert!(server.respond(&mut session, "guess 11").starts_with("error"));
+//! assert_eq!(server.respond(&mut session, "quit"), "bye");
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Sender, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand;
+use rand::{Rng, StdRng};
+
+use gameplay::{KeyPegs, ParseError, Pattern, Rules, Shield, seeded_rng, shield_with};
+
+
+/// Longer lines from a client end its connection.
+pub const MAX_LINE: usize = 1024;
+
+/// The best scores kept; others are forgotten.
+const MAX_SCORES: usize = 100;
+
+/// How long games are kept once over, and once their player stops
+/// guessing; a client silent for as long is hung up on.
+const KEEP_OVER: Duration = Duration::from_secs(60);
+const KEEP_IDLE: Duration = Duration::from_secs(60 * 60);
+
+/// More clients at once are turned away.
+pub const MAX_CLIENTS: usize = 256;
+
+
+/// A client's session and line, and where to send the answer and
+/// the session after.
+type Request = (Option<Token>, String, Sender<(Option<Token>, String)>);
+
+
+/// What a player knows their game by: random, unlike the secrets,
+/// so that knowing the seed doesn't tell it, and written as 16 hex
+/// digits.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct Token(u64);
+
+impl Display for Token {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for Token {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Token, ()> {
+        if s.len() != 16 {
+            return Err(());
+        }
+        u64::from_str_radix(s, 16).map(Token).map_err(|_| ())
+    }
+}
+
+
+struct Game {
+    player: String,
+    codemaker: Shield,
+    /// Kept only to reveal when the game is lost.
+    secret: Pattern,
+    turns: usize,
+    won: bool,
+    /// When the game started or was last played.
+    played: Instant,
+}
+
+/// The games being played and played out, by token, and the best
+/// scores of those won.
+pub struct Server {
+    rules: Rules,
+    rows: usize,
+    rng: StdRng,
+    games: BTreeMap<Token, Game>,
+    /// Turns, then the order of winning, then the player.
+    scores: Vec<(usize, u64, String)>,
+    wins: u64,
+    /// How long games are kept once over, and once their player
+    /// stops guessing.
+    keep_over: Duration,
+    keep_idle: Duration,
+}
+
+impl Server {
+    /// Games by the rules, each of as many rows, with secrets chosen
+    /// as the seed determines. Games are kept a minute once over,
+    /// an hour if their player stops guessing.
+    pub fn new(rules: Rules, rows: usize, seed: u64) -> Server {
+        Server {
+            rules,
+            rows,
+            rng: seeded_rng(seed),
+            games: BTreeMap::new(),
+            scores: vec![],
+            wins: 0,
+            keep_over: KEEP_OVER,
+            keep_idle: KEEP_IDLE,
+        }
+    }
+
+    /// Keep games for so long once over, and once their player stops
+    /// guessing, rather than a minute and an hour.
+    pub fn keep(self, over: Duration, idle: Duration) -> Server {
+        Server {
+            keep_over: over,
+            keep_idle: idle,
+            ..self
+        }
+    }
+
+    /// The answer to a command from a client, whose session is the
+    /// game it's playing, if any.
+    pub fn respond(&mut self, session: &mut Option<Token>, line: &str) -> String {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let answer = match words.as_slice() {
+            ["new"] => Ok(self.new_session("player", session)),
+            ["new", player] => Ok(self.new_session(player, session)),
+            ["join", token] => match token.parse().ok().filter(|token| self.games.contains_key(token)) {
+                Some(token) => {
+                    *session = Some(token);
+                    Ok(format!("game {}", token))
+                }
+                None => Err(format!("no game {}", token)),
+            },
+            ["guess", guess] => match *session {
+                Some(token) => self.guess(token, guess).map(|turn| turn.to_string()).map_err(|e| e.to_string()),
+                None => Err("no game; new or join first".to_string()),
+            },
+            ["scores"] => {
+                let scores: Vec<String> = self.scores().iter().map(|&(ref player, turns)| format!(" {}:{}", player, turns)).collect();
+                Ok(format!("scores{}", scores.concat()))
+            }
+            ["quit"] => Ok("bye".to_string()),
+            [] => Err("no command".to_string()),
+            _ => Err(format!("unknown command: {}", line.trim())),
+        };
+        answer.unwrap_or_else(|reason| format!("error {}", reason))
+    }
+
+    fn new_session(&mut self, player: &str, session: &mut Option<Token>) -> String {
+        let token = self.start(player);
+        *session = Some(token);
+        format!("game {}", token)
+    }
+
+    /// Start a game for a player, with a new secret; its token.
+    /// Games kept long enough are dropped first.
+    pub fn start(&mut self, player: &str) -> Token {
+        self.evict();
+        let mut tokens = rand::thread_rng();
+        let token = loop {
+            let token = Token(tokens.gen());
+            if !self.games.contains_key(&token) {
+                break token;
+            }
+        };
+        let secret = self.rules.random(&mut self.rng);
+        self.games.insert(token, Game {
+            player: player.to_string(),
+            codemaker: shield_with(self.rules, secret),
+            secret,
+            turns: 0,
+            won: false,
+            played: Instant::now(),
+        });
+        token
+    }
+
+    /// Drop games over for longer than `keep_over`, and those not
+    /// played for longer than `keep_idle`.
+    fn evict(&mut self) {
+        let rows = self.rows;
+        let (keep_over, keep_idle) = (self.keep_over, self.keep_idle);
+        self.games.retain(|_, game| {
+            let over = game.won || game.turns >= rows;
+            game.played.elapsed() < if over { keep_over } else { keep_idle }
+        });
+    }
+
+    /// Play a guess, written as under the rules, in a game.
+    pub fn guess(&mut self, token: Token, guess: &str) -> Result<Turn, PlayError> {
+        let (rules, rows) = (self.rules, self.rows);
+        let game = self.games.get_mut(&token).ok_or(PlayError::NoGame(token))?;
+        if game.won || game.turns >= rows {
+            return Err(PlayError::GameOver);
+        }
+        let guess = rules.parse(guess).map_err(PlayError::Pattern)?;
+        if !rules.is_guess(&guess) {
+            return Err(PlayError::NotAllowed);
+        }
+
+        let response = (game.codemaker)(&guess);
+        game.turns += 1;
+        game.won = rules.is_win(&response);
+        game.played = Instant::now();
+        if game.won {
+            self.wins += 1;
+            self.scores.push((game.turns, self.wins, game.player.clone()));
+            self.scores.sort();
+            self.scores.truncate(MAX_SCORES);
+        }
+        Ok(Turn {
+            response,
+            turns: game.turns,
+            won: game.won,
+            revealed: if !game.won && game.turns >= rows { Some(game.secret) } else { None },
+        })
+    }
+
+    /// The winners and their turns, fewest first, up to the best
+    /// hundred.
+    pub fn scores(&self) -> Vec<(String, usize)> {
+        self.scores.iter().map(|&(turns, _, ref player)| (player.clone(), turns)).collect()
+    }
+}
+
+
+/// What came of a guess: the key pegs, and whether the game is won,
+/// or lost, revealing the code.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Turn {
+    pub response: KeyPegs,
+    /// Guesses played so far, this one included.
+    pub turns: usize,
+    pub won: bool,
+    pub revealed: Option<Pattern>,
+}
+
+/// As answered to `guess`, e.g. "BW", "BBBB win 4" or "- lose 3632".
+impl Display for Turn {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if self.response == KeyPegs::new() {
+            fmt.write_str("-")?;
+        } else {
+            write!(fmt, "{}", self.response)?;
+        }
+        match self.revealed {
+            _ if self.won => write!(fmt, " win {}", self.turns),
+            Some(secret) => write!(fmt, " lose {}", secret),
+            None => Ok(()),
+        }
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PlayError {
+    NoGame(Token),
+    /// Won, or out of rows.
+    GameOver,
+    Pattern(ParseError),
+    /// A pattern the rules allow as a code, but not as a guess.
+    NotAllowed,
+}
+
+impl Display for PlayError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match *self {
+            PlayError::NoGame(token) => write!(fmt, "no game {}", token),
+            PlayError::GameOver => fmt.write_str("game over"),
+            PlayError::Pattern(ref e) => write!(fmt, "{}", e),
+            PlayError::NotAllowed => fmt.write_str("not allowed as a guess"),
+        }
+    }
+}
+
+impl Error for PlayError {}
+
+
+/// Serve games to clients as they connect, until the listener
+/// fails. One thread holds the games, shields and all; each client,
+/// up to `MAX_CLIENTS` at once, has a thread that passes it lines.
+pub fn serve(listener: TcpListener, rules: Rules, rows: usize, seed: u64) -> io::Result<()> {
+    let (requests, incoming) = channel::<Request>();
+    thread::spawn(move || {
+        let mut server = Server::new(rules, rows, seed);
+        for (mut session, line, reply) in incoming {
+            let answer = server.respond(&mut session, &line);
+            let _ = reply.send((session, answer));
+        }
+    });
+
+    let clients = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        if clients.fetch_add(1, Ordering::SeqCst) >= MAX_CLIENTS {
+            clients.fetch_sub(1, Ordering::SeqCst);
+            let _ = writeln!(stream, "error too many clients");
+            continue;
+        }
+        let requests = requests.clone();
+        let clients = clients.clone();
+        thread::spawn(move || {
+            let _ = client(stream, &requests);
+            clients.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+    Ok(())
+}
+
+/// Pass a client's lines to the server, and the answers back.
+fn client(stream: TcpStream, requests: &Sender<Request>) -> io::Result<()> {
+    stream.set_read_timeout(Some(KEEP_IDLE))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let (reply, answers) = channel();
+    let mut session = None;
+    loop {
+        let mut line = String::new();
+        if reader.by_ref().take(MAX_LINE as u64 + 1).read_line(&mut line)? == 0 {
+            break;
+        }
+        if line.len() > MAX_LINE {
+            writeln!(writer, "error line too long")?;
+            break;
+        }
+        let line = line.trim_end_matches(&['\r', '\n'][..]).to_string();
+        if requests.send((session, line.clone(), reply.clone())).is_err() {
+            break;
+        }
+        let (updated, answer) = match answers.recv() {
+            Ok(answered) => answered,
+            Err(_) => break,
+        };
+        session = updated;
+        writeln!(writer, "{}", answer)?;
+        if line.trim() == "quit" {
+            break;
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+
+    use gameplay::Rules;
+    use solver::{PatternSet, Solver};
+    use super::{MAX_LINE, Server, serve};
+
+    #[test]
+    fn rows_and_scores() {
+        let mut server = Server::new(Rules::new(2, 2), 2, 1);
+        let (mut alice, mut bob) = (None, None);
+        server.respond(&mut alice, "new alice");
+        server.respond(&mut bob, "new bob");
+        assert!(server.respond(&mut None, "guess 12").starts_with("error"));
+
+        // Two rows: the game is over after two guesses, if not one.
+        let answers: Vec<String> = ["11", "12", "21", "22"]
+            .iter()
+            .map(|g| server.respond(&mut alice, &format!("guess {}", g)))
+            .collect();
+        let over = answers.iter().position(|a| a.contains(" win ") || a.contains(" lose ")).unwrap();
+        assert!(over < 2);
+        assert!(answers[over + 1..].iter().all(|a| a == "error game over"));
+
+        let mut again = None;
+        let bobs = bob.unwrap().to_string();
+        assert_eq!(server.respond(&mut again, &format!("join {}", bobs)), format!("game {}", bobs));
+        assert_eq!(again, bob);
+        assert!(server.respond(&mut again, "join 2").starts_with("error no game"));
+        let unknown = if bobs == "0000000000000000" { "0000000000000001" } else { "0000000000000000" };
+        assert!(server.respond(&mut again, &format!("join {}", unknown)).starts_with("error no game"));
+        assert!(server.respond(&mut None, "scores").starts_with("scores"));
+    }
+
+    #[test]
+    fn eviction() {
+        let mut server = Server::new(Rules::new(2, 2), 1, 1).keep(Duration::from_secs(0), Duration::from_secs(3600));
+        let (mut alice, mut bob) = (None, None);
+        server.respond(&mut alice, "new alice");
+        server.respond(&mut bob, "new bob");
+        // One row: alice's game is over after a guess, and dropped
+        // once the next game starts; bob's, not yet played, is kept.
+        let first = server.respond(&mut alice, "guess 12");
+        server.respond(&mut None, "new carol");
+        assert!(server.respond(&mut alice, "guess 12").starts_with("error no game"));
+        assert!(!server.respond(&mut bob, "guess 12").starts_with("error"));
+        if first.contains(" win ") {
+            assert_eq!(server.scores(), vec![("alice".to_string(), 1)]);
+        }
+    }
+
+    #[test]
+    fn localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, Rules::default(), 12, 42));
+
+        // Clients at once, each solving its own game.
+        let clients: Vec<_> = ["alice", "bob", "carol"]
+            .iter()
+            .map(|&player| {
+                thread::spawn(move || {
+                    let stream = TcpStream::connect(address).unwrap();
+                    let mut writer = stream.try_clone().unwrap();
+                    let mut lines = BufReader::new(stream).lines();
+                    let mut ask = |command: String| {
+                        writeln!(writer, "{}", command).unwrap();
+                        lines.next().unwrap().unwrap()
+                    };
+
+                    assert!(ask(format!("new {}", player)).starts_with("game "));
+                    let rules = Rules::default();
+                    let mut breaker = Solver::observing(PatternSet::codes(rules));
+                    loop {
+                        let guess = breaker.next_guess();
+                        let answer = ask(format!("guess {}", guess));
+                        let pegs = answer.split_whitespace().next().unwrap();
+                        if answer.contains(" win ") {
+                            break;
+                        }
+                        breaker.observe(guess, rules.parse_response(if pegs == "-" { "" } else { pegs }).unwrap());
+                    }
+                    assert_eq!(ask("quit".to_string()), "bye");
+                })
+            })
+            .collect();
+        for client in clients {
+            client.join().unwrap();
+        }
+
+        let stream = TcpStream::connect(address).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        writeln!(writer, "scores").unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let scores = lines.next().unwrap().unwrap();
+        assert_eq!(scores.split_whitespace().count(), 4);
+        assert!(scores.contains("alice:") && scores.contains("bob:") && scores.contains("carol:"));
+
+        writeln!(writer, "new {}", "x".repeat(MAX_LINE)).unwrap();
+        assert_eq!(lines.next().unwrap().unwrap(), "error line too long");
+        assert!(lines.next().is_none());
+    }
+}
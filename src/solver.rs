@@ -92,21 +92,139 @@
 //!
 //! TODO: test for step 6
 //!
+//! Knuth's convention of choosing the least guess makes the solver
+//! entirely predictable; an adversarial codemaker can exploit
+//! that. Other `TieBreak` policies choose among the maximal guesses
+//! at random, or by how many distinct responses they allow.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::{Pattern, shield, seeded_rng};
+//! use self::mastermind::solver::{Solver, TieBreak};
+//!
+//! let code = Pattern::from_digits(['3', '6', '2', '5']);
+//! let breaker = Solver::new(shield(code))
+//!     .tie_break(TieBreak::RandomInS, Box::new(seeded_rng(42)));
+//! assert_eq!(breaker.last(), Some(code));
+//! ```
+//!
 //! [Knuth's five guess algorithm][wp5]
 //! [wp5]: http://en.wikipedia.org/wiki/Mastermind_%28board_game%29#Five-guess_algorithm
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::cmp::Ordering::*;
+use std::mem;
 use bit_set::BitSet;
 use bit_vec::BitVec;
+use rand::Rng;
 
-use gameplay::{Pattern, KeyPegs, Shield};
+use gameplay::{Pattern, KeyPegs, Rules, Scorer, Shield};
 
 
 pub struct Solver {
+    rules: Rules,
     codemaker: Shield,
     pub guessed: Vec<Pattern>,
+    /// The response to each guess, but perhaps the last, if it has
+    /// been played and not yet responded to.
+    responses: Vec<KeyPegs>,
     pub s: PatternSet,
+    /// S before each response observed, to undo it; and each
+    /// response undone, with S after it and any guess played after
+    /// it, to redo it. S is a bit set, so a snapshot costs a bit per
+    /// pattern of the rules.
+    history: Vec<PatternSet>,
+    undone: Vec<(KeyPegs, PatternSet, Option<Pattern>)>,
+    /// What to guess from, if not everything the rules allow.
+    guesses: Option<PatternSet>,
+    hints: Vec<Hint>,
+    strategy: Strategy,
+    tie_break: TieBreak,
+    rng: Option<Box<dyn Rng>>,
+}
+
+/// Side information the codemaker reveals about the code, as in
+/// Number Mastermind, where the sum of its digits is known.
+///
+/// ```rust
+/// use self::mastermind::gameplay::{Rules, shield};
+/// use self::mastermind::solver::{Hint, Solver};
+///
+/// let rules = Rules::new(4, 10);
+/// let code = rules.parse("7290").unwrap();
+/// assert_eq!(code.digit_sum(), Some(18));
+///
+/// let breaker = Solver::with_rules(rules, shield(code)).hint(Hint::DigitSum(18));
+/// assert_eq!(breaker.s.len(), 670);
+/// assert_eq!(breaker.last(), Some(code));
+///
+/// let no_sevens = Hint::Predicate(Box::new(|p| !p.code_pegs().contains(&6)));
+/// assert!(!no_sevens.holds(&code));
+/// ```
+pub enum Hint {
+    /// The digits of the code, as written, add up to this.
+    DigitSum(u32),
+    Predicate(Box<dyn Fn(&Pattern) -> bool>),
+}
+
+impl Hint {
+    pub fn holds(&self, code: &Pattern) -> bool {
+        match *self {
+            Hint::DigitSum(sum) => code.digit_sum() == Some(sum),
+            Hint::Predicate(ref predicate) => predicate(code),
+        }
+    }
+}
+
+
+/// A solver's state apart from its codemaker: the codes it started
+/// from, as narrowed by any hints, and the guesses it has made, each
+/// with its response, but perhaps the last, played and not yet
+/// responded to.
+///
+/// With the `serde` feature, it serializes with its rules, and its
+/// patterns and responses written as under them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Knowledge {
+    pub rules: Rules,
+    pub codes: PatternSet,
+    pub guessed: Vec<Pattern>,
+    pub responses: Vec<KeyPegs>,
+}
+
+impl Knowledge {
+    fn is_valid(&self) -> bool {
+        self.responses.len() <= self.guessed.len() && self.guessed.len() <= self.responses.len() + 1
+    }
+}
+
+
+/// How to choose each guess.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Strategy {
+    /// Knuth's minimax, over all the guesses allowed.
+    #[default]
+    Minimax,
+    /// The least member of S: always a guess that could win, and
+    /// cheap enough for large code spaces.
+    Consistent,
+}
+
+
+/// How to select the next guess from the set of guesses with the
+/// maximum score.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TieBreak {
+    /// Knuth's convention: the least member of S, if any; otherwise
+    /// the least guess.
+    #[default]
+    LeastIndex,
+    /// A random member of S, if any; otherwise any at random.
+    RandomInS,
+    /// Any at random, whether in S or not.
+    RandomMax,
+    /// The guess that splits S into the most parts, i.e. allows
+    /// the most distinct responses; ties go as with `LeastIndex`.
+    MostParts,
 }
 
 impl Solver {
@@ -116,10 +234,67 @@ impl Solver {
     }
 
     pub fn new(codemaker: Shield) -> Solver {
+        Solver::with_rules(Rules::default(), codemaker)
+    }
+
+    /// Play by other rules, starting with S as all the codes they
+    /// allow, and guessing only what they allow.
+    pub fn with_rules(rules: Rules, codemaker: Shield) -> Solver {
+        Solver::with_codes(PatternSet::codes(rules), codemaker)
+    }
+
+    /// Start with S as the given codes, such as the words of a
+    /// dictionary, rather than all the rules allow.
+    pub fn with_codes(codes: PatternSet, codemaker: Shield) -> Solver {
         Solver {
-            codemaker: codemaker,
-            s: Solver::possible_codes(),
+            rules: codes.rules(),
+            codemaker,
+            s: codes,
             guessed: vec![],
+            responses: vec![],
+            history: vec![],
+            undone: vec![],
+            guesses: None,
+            hints: vec![],
+            strategy: Strategy::default(),
+            tie_break: TieBreak::default(),
+            rng: None,
+        }
+    }
+
+    /// A solver driven by `next_guess` and `observe` rather than by
+    /// a codemaker; as an iterator, it panics.
+    pub fn observing(codes: PatternSet) -> Solver {
+        Solver::with_codes(codes, Box::new(|_: &Pattern| panic!("no codemaker; observe responses instead")))
+    }
+
+    /// Guess only from the given set, e.g. the codes of a dictionary,
+    /// starting with a minimax guess rather than the rules' initial
+    /// guess.
+    pub fn guesses(self, guesses: PatternSet) -> Solver {
+        Solver { guesses: Some(guesses), ..self }
+    }
+
+    /// Take side information about the code into account, removing
+    /// from S any code that doesn't fit it; the first guess is then
+    /// by minimax rather than the rules' initial guess.
+    pub fn hint(mut self, hint: Hint) -> Solver {
+        self.s.filter_with(&|p: &Pattern| hint.holds(p));
+        self.hints.push(hint);
+        self
+    }
+
+    pub fn strategy(self, strategy: Strategy) -> Solver {
+        Solver { strategy, ..self }
+    }
+
+    /// Select among maximal guesses by the given policy, drawing on
+    /// `rng` for the random ones.
+    pub fn tie_break(self, policy: TieBreak, rng: Box<dyn Rng>) -> Solver {
+        Solver {
+            tie_break: policy,
+            rng: Some(rng),
+            ..self
         }
     }
 
@@ -132,41 +307,179 @@ impl Solver {
     /// - 3. Play the guess to get a response of colored and white pegs.
     /// - 4. If the response is four colored pegs, the game is won, the algorithm terminates.
     /// - 5. Otherwise, remove from S any code that would not
-    ///   give the same response if it (the guess) were the code.
-    ///   From the set of guesses with the maximum score, select one as
-    ///   the next guess ...
+    ///      give the same response if it (the guess) were the code.
+    ///
+    /// From the set of guesses with the maximum score, select one as
+    /// the next guess ...
     ///
     /// Return Some(guess) or None if we already won.
-    pub fn play(self: &mut Self) -> Option<Pattern> {
-        if self.guessed.is_empty() {
-            let guess = Self::initial_guess();
-            self.guessed.push(guess);
-            Some(guess)
-        } else {
+    pub fn play(&mut self) -> Option<Pattern> {
+        if self.is_pending() {
             let prev = self.last_guess();
             // 3. Play the guess to get a response of colored and white pegs.
             let response = (self.codemaker)(&prev);
+            self.observe(prev, response);
+        }
 
-            // If the response is four colored pegs, the game is won, the algorithm terminates.
-            if response.win() {
-                None
-            } else {
-                // 5. Otherwise, remove from S any code that would not
-                // give the same response if it (the guess) were the code.
-                self.retain_same_response(response);
+        // If the response is four colored pegs, the game is won, the algorithm terminates.
+        if self.is_solved() {
+            return None;
+        }
 
-                // From the set of guesses with the maximum score, select one as
-                // the next guess ...
-                let ng = self.next_guess();
-                self.guessed.push(ng);
+        // From the set of guesses with the maximum score, select one as
+        // the next guess ...
+        let guess = self.next_guess();
+        self.guessed.push(guess);
+        Some(guess)
+    }
 
-                Some(ng)
-            }
+    /// Learn the response to a guess, the solver's or any other:
+    /// 5. remove from S any code that would not give the same
+    /// response if it (the guess) were the code. A guess played but
+    /// not yet responded to is replaced by this one.
+    ///
+    /// With `next_guess`, this drives the solver from outside, with
+    /// responses from wherever they come, rather than by asking its
+    /// codemaker.
+    ///
+    /// ```rust
+    /// use self::mastermind::gameplay::{Rules, shield};
+    /// use self::mastermind::solver::{PatternSet, Solver};
+    ///
+    /// let rules = Rules::default();
+    /// let code = rules.parse("3632").unwrap();
+    /// let mut breaker = Solver::observing(PatternSet::codes(rules));
+    /// while !breaker.is_solved() {
+    ///     let guess = breaker.next_guess();
+    ///     breaker.observe(guess, rules.score(code, guess));
+    /// }
+    /// assert_eq!(breaker.guessed, Solver::new(shield(code)).collect::<Vec<_>>());
+    /// ```
+    pub fn observe(&mut self, guess: Pattern, response: KeyPegs) {
+        if self.is_pending() {
+            self.guessed.pop();
+        }
+        self.history.push(self.s.clone());
+        self.undone.clear();
+        self.guessed.push(guess);
+        self.retain_same_response(response);
+        self.responses.push(response);
+    }
+
+    /// Has a guess been played, but its response not observed?
+    fn is_pending(&self) -> bool {
+        self.responses.len() < self.guessed.len()
+    }
+
+    /// Has a guess been observed to win?
+    pub fn is_solved(&self) -> bool {
+        self.responses.last().is_some_and(|response| self.rules.is_win(response))
+    }
+
+    /// Take back the last response observed, restoring S, along with
+    /// any guess played after it. Return the guess it was the
+    /// response to, which is then to be answered again.
+    ///
+    /// ```rust
+    /// use self::mastermind::gameplay::{Pattern, shield};
+    /// use self::mastermind::solver::Solver;
+    ///
+    /// let mut breaker = Solver::new(shield("3632".parse().unwrap()));
+    /// let first = breaker.play().unwrap();
+    /// let second = breaker.play().unwrap();
+    /// let s = breaker.s.clone();
+    ///
+    /// assert_eq!(breaker.undo(), Some(first));
+    /// assert_eq!((breaker.guessed.clone(), breaker.s.len()), (vec![first], 1296));
+    /// assert_eq!(breaker.undo(), None);
+    /// assert_eq!(breaker.redo(), Some(first));
+    /// assert_eq!((breaker.guessed.clone(), breaker.s.clone()), (vec![first, second], s));
+    /// assert_eq!(breaker.redo(), None);
+    /// ```
+    pub fn undo(&mut self) -> Option<Pattern> {
+        let before = self.history.pop()?;
+        let next = if self.is_pending() { self.guessed.pop() } else { None };
+        let response = self.responses.pop().expect("a response for each snapshot");
+        let after = mem::replace(&mut self.s, before);
+        self.undone.push((response, after, next));
+        Some(self.last_guess())
+    }
+
+    /// Observe again the last response taken back, if nothing has
+    /// been observed since. Return the guess it was the response to.
+    pub fn redo(&mut self) -> Option<Pattern> {
+        let (response, after, next) = self.undone.pop()?;
+        let guess = self.last_guess();
+        self.history.push(mem::replace(&mut self.s, after));
+        self.responses.push(response);
+        self.guessed.extend(next);
+        Some(guess)
+    }
+
+    /// What the solver has learned: its guesses and their responses,
+    /// and the codes it started from. Unlike the solver, this can be
+    /// cloned, and saved to `resume` the game later.
+    pub fn knowledge(&self) -> Knowledge {
+        Knowledge {
+            rules: self.rules,
+            codes: self.history.first().unwrap_or(&self.s).clone(),
+            guessed: self.guessed.clone(),
+            responses: self.responses.clone(),
         }
     }
 
-    pub fn last_guess(self: &Self) -> Pattern {
-        *self.guessed.last().expect("guesses starts with 1 and never shrinks")
+    /// Continue a game from what was learned, with the given
+    /// codemaker; S, and the history to undo, are as they were.
+    /// Strategy, guesses and tie-breaks are to be set again.
+    ///
+    /// ```rust
+    /// use self::mastermind::gameplay::{Pattern, shield};
+    /// use self::mastermind::solver::Solver;
+    ///
+    /// let code: Pattern = "3632".parse().unwrap();
+    /// let mut breaker = Solver::new(shield(code));
+    /// breaker.play();
+    /// breaker.play();
+    /// let paused = breaker.knowledge();
+    /// assert_eq!(paused.responses.len(), 1);
+    ///
+    /// let resumed = Solver::resume(paused.clone(), shield(code));
+    /// assert_eq!(resumed.s, breaker.s);
+    /// assert_eq!(resumed.knowledge(), paused);
+    /// assert_eq!(resumed.last(), breaker.last());
+    /// ```
+    pub fn resume(knowledge: Knowledge, codemaker: Shield) -> Solver {
+        assert!(knowledge.is_valid(), "a response for each guess, but perhaps the last");
+        let mut solver = Solver::with_codes(knowledge.codes, codemaker);
+        for (&guess, &response) in knowledge.guessed.iter().zip(&knowledge.responses) {
+            solver.observe(guess, response);
+        }
+        solver.guessed.extend(knowledge.guessed.get(knowledge.responses.len()));
+        solver
+    }
+
+    /// The rules' initial guess, unless S or the guesses have been
+    /// narrowed, or the strategy calls for a consistent guess.
+    pub fn first_guess(&mut self) -> Pattern {
+        if self.guesses.is_none() && self.hints.is_empty() && self.strategy == Strategy::Minimax {
+            self.rules.initial_guess()
+        } else {
+            self.best_guess()
+        }
+    }
+
+    /// The guess to play next: the first guess, if none has been
+    /// played; otherwise, as below.
+    pub fn next_guess(&mut self) -> Pattern {
+        if self.guessed.is_empty() {
+            self.first_guess()
+        } else {
+            self.best_guess()
+        }
+    }
+
+    pub fn last_guess(&self) -> Pattern {
+        *self.guessed.last().expect("a guess played")
     }
 
     // 5. Otherwise, remove from S any code that would not
@@ -174,7 +487,8 @@ impl Solver {
     pub fn retain_same_response(&mut self, response: KeyPegs) {
         let the_guess = self.last_guess();
 
-        self.s.filter_with(&|p: &Pattern| the_guess.score(*p) == response)
+        let rules = self.rules;
+        self.s.filter_with(&|p: &Pattern| rules.score(*p, the_guess) == response)
     }
 
     /// - 6. Apply minimax technique to find a next guess as follows ...
@@ -182,7 +496,11 @@ impl Solver {
     ///      select one as
     ///      the next guess, choosing a member of S whenever
     ///      possible.
-    pub fn next_guess(&self) -> Pattern {
+    fn best_guess(&mut self) -> Pattern {
+        if self.strategy == Strategy::Consistent {
+            return self.s.iter().next().expect("no code is consistent");
+        }
+
         // Knuth follows the convention of choosing the guess with the
         // least numeric value e.g. 2345 is lower than 3456.
         let sorted = |ps: Vec<Pattern>| {
@@ -192,71 +510,180 @@ impl Solver {
         };
         let next_guesses = sorted(self.max_score_guesses());
 
-        // ... choosing a member of S whenever possible.
-        let next_in_s = next_guesses.iter().find(|g| self.s.contains(g));
+        let in_s: Vec<Pattern> = next_guesses.iter().cloned().filter(|g| self.s.contains(g)).collect();
+        let least = |candidates: &[Pattern]| {
+            // ... choosing a member of S whenever possible.
+            match candidates.iter().find(|g| self.s.contains(g)) {
+                Some(g) => *g,
+                None => candidates[0], // TODO: .expect()
+            }
+        };
 
-        match next_in_s {
-            Some(g) => *g,
-            None => next_guesses[0], // TODO: .expect()
+        match self.tie_break {
+            TieBreak::LeastIndex => least(&next_guesses),
+            TieBreak::RandomInS if !in_s.is_empty() => self.choose(&in_s),
+            TieBreak::RandomInS | TieBreak::RandomMax => self.choose(&next_guesses),
+            TieBreak::MostParts => {
+                let parts: Vec<usize> = next_guesses.iter().map(|g| self.s.hit_counts(*g).len()).collect();
+                let most = *parts.iter().max().expect("no maximal guesses?");
+                let with_most: Vec<Pattern> = next_guesses.iter()
+                    .zip(parts)
+                    .filter(|&(_, n)| n == most)
+                    .map(|(g, _)| *g)
+                    .collect();
+                least(&with_most)
+            }
         }
     }
 
+    fn choose(&mut self, candidates: &[Pattern]) -> Pattern {
+        let rng = self.rng.as_mut().expect("random tie-break without an Rng");
+        *rng.choose(candidates).expect("no maximal guesses?")
+    }
+
 
     /// Apply minimax technique to find a next guess as follows ...
-    pub fn max_score_guesses(self: &Self) -> Vec<Pattern> {
-
-        // The score of a guess is the minimum number of possibilities
-        // it might eliminate from S.
-        let minimum_eliminated = |guess: Pattern| {
-            // A single pass through S for each unused code of the 1296 will provide a hit
-            // count for each colored/white peg score found;
-            let s_pass = Pattern::range().filter(|p| self.s.contains(p));
-
-            let peg_scores = s_pass.map(|possibility| guess.score(possibility));
-            let hit_count = {
-                let mut map = HashMap::new();
-
-                for bw in peg_scores {
-                    *(map.entry(bw).or_insert(0)) += 1;
-                }
-                map
-            };
+    pub fn max_score_guesses(&self) -> Vec<Pattern> {
+        // For each possible guess, that is, any unused code of the
+        // 1296 not just those in S ...
+        let unused = |p: &Pattern| !self.guessed.contains(p);
+        match self.guesses {
+            Some(ref guesses) => max_score_guesses(guesses.iter().filter(unused), &self.s),
+            None => max_score_guesses(self.rules.guesses().filter(unused), &self.s),
+        }
+    }
+}
 
-            // the colored/white peg score with the highest hit count
-            // will eliminate the fewest possibilities; calculate the
-            // score of a guess by using "minimum eliminated" = "count
-            // of elements in S" - (minus) "highest hit count".
-            let highest_hit_count = hit_count.values()
-                .max()
-                .expect("no max hit count: empty S? already won?");
-            self.s.len() - highest_hit_count
-        };
 
-        let append = |xs: Vec<Pattern>, x| {
-            let mut v = xs;
-            v.push(x);
-            v
-        };
+/// For each of the given guesses, calculate how many possibilities
+/// in S would be eliminated for each possible colored/white peg
+/// score, as the rules of S give it, and return the guesses with the
+/// maximum score.
+pub fn max_score_guesses<I>(guesses: I, s: &PatternSet) -> Vec<Pattern>
+    where I: Iterator<Item = Pattern>
+{
+    minimax_guesses(&s.rules(), guesses, s)
+}
 
-        // For each possible guess, that is, any unused code of the
-        // 1296 not just those in S, calculate how many possibilities
-        // in S would be eliminated for each possible colored/white
-        // peg score.
-        let unused = |p: &Pattern| !self.guessed.contains(p);
-        let (_, max_scoring_guesses) = Pattern::range()
-            .filter(unused)
-            .fold((0, vec![]), |acc: (usize, Vec<Pattern>), guess| {
-                let (high_score, candidates) = acc;
-                let score = minimum_eliminated(guess);
-                match score.cmp(&high_score) {
-                    Greater => (score, vec![guess]),
-                    Equal => (score, append(candidates, guess)),
-                    _ => (high_score, candidates),
-                }
-            });
-
-        max_scoring_guesses
+/// As `max_score_guesses`, for any way of responding.
+pub fn minimax_guesses<F, I>(scorer: &F, guesses: I, s: &PatternSet) -> Vec<Pattern>
+    where F: Scorer,
+          I: Iterator<Item = Pattern>
+{
+    // The score of a guess is the minimum number of possibilities
+    // it might eliminate from S.
+    let possibilities: Vec<Pattern> = s.iter().collect();
+    let mut hit_count = vec![0usize; scorer.responses()];
+    let mut minimum_eliminated = |guess: Pattern| {
+        // A single pass through S for each unused code of the 1296 will provide a hit
+        // count for each colored/white peg score found;
+        // (counted in a table rather than a map, as this is the inner loop)
+        for count in hit_count.iter_mut() {
+            *count = 0;
+        }
+        for &possibility in &possibilities {
+            hit_count[scorer.response_index(&scorer.score(possibility, guess))] += 1;
+        }
+
+        // the colored/white peg score with the highest hit count
+        // will eliminate the fewest possibilities; calculate the
+        // score of a guess by using "minimum eliminated" = "count
+        // of elements in S" - (minus) "highest hit count".
+        let highest_hit_count = *hit_count.iter().max().expect("no max hit count: empty S? already won?");
+        possibilities.len() - highest_hit_count
+    };
+
+    let append = |xs: Vec<Pattern>, x| {
+        let mut v = xs;
+        v.push(x);
+        v
+    };
+
+    let (_, max_scoring_guesses) = guesses
+        .fold((0, vec![]), |acc: (usize, Vec<Pattern>), guess| {
+            let (high_score, candidates) = acc;
+            let score = minimum_eliminated(guess);
+            match score.cmp(&high_score) {
+                Greater => (score, vec![guess]),
+                Equal => (score, append(candidates, guess)),
+                _ => (high_score, candidates),
+            }
+        });
+
+    max_scoring_guesses
+}
+
+
+/// The expected information, in bits, of the response to a guess,
+/// if each member of S is equally likely to be the code.
+pub fn entropy<F: Scorer>(scorer: &F, guess: Pattern, s: &PatternSet) -> f64 {
+    let mut hit_count = vec![0usize; scorer.responses()];
+    for possibility in s.iter() {
+        hit_count[scorer.response_index(&scorer.score(possibility, guess))] += 1;
+    }
+    let total = s.len() as f64;
+    hit_count.iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// The guesses whose responses are, on average, most informative.
+pub fn max_entropy_guesses<F, I>(scorer: &F, guesses: I, s: &PatternSet) -> Vec<Pattern>
+    where F: Scorer,
+          I: Iterator<Item = Pattern>
+{
+    // Equal partitions may sum in a different order; round off.
+    const EPSILON: f64 = 1e-9;
+    let mut best = (0.0, vec![]);
+    for guess in guesses {
+        let bits = entropy(scorer, guess, s);
+        if bits > best.0 + EPSILON {
+            best = (bits, vec![guess]);
+        } else if bits > best.0 - EPSILON {
+            best.1.push(guess);
+        }
+    }
+    best.1
+}
+
+
+/// The members of S by their response to the guess.
+pub fn partition<F: Scorer>(scorer: &F, guess: Pattern, s: &PatternSet) -> BTreeMap<F::Response, PatternSet> {
+    let mut parts = BTreeMap::new();
+    for code in s.iter() {
+        parts.entry(scorer.score(code, guess)).or_insert_with(|| PatternSet::empty(s.rules())).insert(code);
     }
+    parts
+}
+
+
+/// Play by minimax against the codemaker, with any way of
+/// responding, guessing from the given set, and preferring members of
+/// S, then the least; return the guesses, ending with the code.
+pub fn solve<F: Scorer>(scorer: &F,
+                        codes: PatternSet,
+                        guesses: &PatternSet,
+                        codemaker: &dyn Fn(&Pattern) -> F::Response)
+                        -> Vec<Pattern> {
+    let mut s = codes;
+    let mut guessed = vec![];
+    while !s.is_empty() {
+        let mut candidates = minimax_guesses(scorer, guesses.iter().filter(|g| !guessed.contains(g)), &s);
+        candidates.sort_by_key(|g| (!s.contains(g), *g));
+        let guess = candidates[0];
+        guessed.push(guess);
+
+        let response = codemaker(&guess);
+        if scorer.is_win(&response) {
+            break;
+        }
+        s.filter_with(&|p: &Pattern| scorer.score(*p, guess) == response);
+    }
+    guessed
 }
 
 
@@ -269,8 +696,31 @@ impl Iterator for Solver {
 }
 
 
+/// Beyond so many patterns, a set that starts empty keeps just its
+/// members rather than a bit for each pattern: 26^6 bits for the
+/// words of a dictionary of six letters would be 38 MB.
+const DENSE_LIMIT: u32 = 1 << 20;
+
+
+#[derive(Debug, Clone, Default)]
 pub struct PatternSet {
-    indexes: BitSet,
+    rules: Rules,
+    indexes: Indexes,
+}
+
+/// Members by lexical index.
+#[derive(Debug, Clone)]
+enum Indexes {
+    /// A bit for each pattern the rules allow.
+    Dense(BitSet),
+    /// Just the members, e.g. the words of a dictionary.
+    Sparse(BTreeSet<u32>),
+}
+
+impl Default for Indexes {
+    fn default() -> Self {
+        Indexes::Dense(BitSet::new())
+    }
 }
 
 impl PatternSet {
@@ -278,24 +728,325 @@ impl PatternSet {
         let all_vec = BitVec::from_elem(Pattern::cardinality() as usize, true);
         let all_ix = BitSet::from_bit_vec(all_vec);
 
-        PatternSet { indexes: all_ix }
+        PatternSet {
+            rules: Rules::default(),
+            indexes: Indexes::Dense(all_ix),
+        }
+    }
+
+    /// All the codes the rules allow.
+    pub fn codes(rules: Rules) -> PatternSet {
+        let mut s = PatternSet {
+            rules,
+            indexes: Indexes::Dense(BitSet::with_capacity(rules.cardinality() as usize)),
+        };
+        for p in rules.codes() {
+            s.insert(p);
+        }
+        s
+    }
+
+    /// Just the given codes, e.g. the words of a dictionary.
+    pub fn from_codes<I: IntoIterator<Item = Pattern>>(rules: Rules, codes: I) -> PatternSet {
+        let mut s = PatternSet::empty(rules);
+        for p in codes {
+            debug_assert!(rules.is_code(&p));
+            s.insert(p);
+        }
+        s
+    }
+
+    /// No patterns yet; with more than `DENSE_LIMIT` to choose from,
+    /// the set keeps just its members.
+    pub fn empty(rules: Rules) -> PatternSet {
+        let indexes = if rules.cardinality() <= DENSE_LIMIT {
+            Indexes::Dense(BitSet::with_capacity(rules.cardinality() as usize))
+        } else {
+            Indexes::Sparse(BTreeSet::new())
+        };
+        PatternSet { rules, indexes }
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
     }
 
     pub fn len(&self) -> usize {
-        self.indexes.len()
+        match self.indexes {
+            Indexes::Dense(ref bits) => bits.len(),
+            Indexes::Sparse(ref members) => members.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     pub fn contains(&self, p: &Pattern) -> bool {
-        let ix = p.index() as usize;
-        self.indexes.contains(ix)
+        match self.indexes {
+            Indexes::Dense(ref bits) => bits.contains(p.index() as usize),
+            Indexes::Sparse(ref members) => members.contains(&p.index()),
+        }
+    }
+
+    pub fn insert(&mut self, p: Pattern) -> bool {
+        match self.indexes {
+            Indexes::Dense(ref mut bits) => bits.insert(p.index() as usize),
+            Indexes::Sparse(ref mut members) => members.insert(p.index()),
+        }
+    }
+
+    fn remove(&mut self, p: Pattern) -> bool {
+        match self.indexes {
+            Indexes::Dense(ref mut bits) => bits.remove(p.index() as usize),
+            Indexes::Sparse(ref mut members) => members.remove(&p.index()),
+        }
+    }
+
+    /// Members in lexical order.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = Pattern> + '_> {
+        let rules = self.rules;
+        match self.indexes {
+            Indexes::Dense(ref bits) => Box::new(bits.iter().map(move |ix| rules.ith(ix as u32))),
+            Indexes::Sparse(ref members) => Box::new(members.iter().map(move |&ix| rules.ith(ix))),
+        }
+    }
+
+    /// A single pass through the set provides a hit count for each
+    /// colored/white peg score found.
+    pub fn hit_counts(&self, guess: Pattern) -> HashMap<KeyPegs, usize> {
+        let mut map = HashMap::new();
+
+        for possibility in self.iter() {
+            *(map.entry(self.rules.score(possibility, guess)).or_insert(0)) += 1;
+        }
+        map
     }
 
-    pub fn filter_with(&mut self, predicate: &Fn(&Pattern) -> bool) {
-        for p in Pattern::range() {
-            let ix = p.index() as usize;
-            if self.indexes.contains(ix) && !predicate(&p) {
-                self.indexes.remove(ix);
+    pub fn filter_with(&mut self, predicate: &dyn Fn(&Pattern) -> bool) {
+        let rejects: Vec<Pattern> = self.iter().filter(|p| !predicate(p)).collect();
+        for p in rejects {
+            self.remove(p);
+        }
+    }
+}
+
+/// The same rules and members, however they're kept.
+impl PartialEq for PatternSet {
+    fn eq(&self, other: &PatternSet) -> bool {
+        self.rules == other.rules && self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for PatternSet {}
+
+
+/// A pattern set serializes with its rules, and its members written
+/// as under them.
+#[cfg(feature = "serde")]
+mod serialization {
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+    use serde::de;
+
+    use gameplay::{Pattern, Rules};
+    use super::{Knowledge, PatternSet};
+
+    /// A pattern set's members as written under its rules.
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "PatternSet")]
+    struct WrittenSet {
+        rules: Rules,
+        members: Vec<String>,
+    }
+
+    impl Serialize for PatternSet {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            WrittenSet {
+                rules: self.rules,
+                members: self.iter().map(|p| p.to_string()).collect(),
+            }.serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for PatternSet {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<PatternSet, D::Error> {
+            let written = WrittenSet::deserialize(deserializer)?;
+            let mut s = PatternSet::empty(written.rules);
+            for p in &written.members {
+                s.insert(written.rules.parse(p).map_err(de::Error::custom)?);
+            }
+            Ok(s)
+        }
+    }
+
+    /// Patterns and responses as written under the rules, so as to
+    /// be read back under them.
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "Knowledge")]
+    struct Written {
+        rules: Rules,
+        codes: Vec<String>,
+        guessed: Vec<String>,
+        responses: Vec<String>,
+    }
+
+    impl Serialize for Knowledge {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            Written {
+                rules: self.rules,
+                codes: self.codes.iter().map(|p| p.to_string()).collect(),
+                guessed: self.guessed.iter().map(|p| p.to_string()).collect(),
+                responses: self.responses.iter().map(|k| k.to_string()).collect(),
+            }.serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Knowledge {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Knowledge, D::Error> {
+            let written = Written::deserialize(deserializer)?;
+            let rules = written.rules;
+            // Parsing allows codes and guesses alike; each must be the
+            // one it's meant to be.
+            let patterns = |written: &[String], allowed: fn(&Rules, &Pattern) -> bool, what: &str| {
+                written.iter()
+                    .map(|p| match rules.parse(p) {
+                        Ok(p) if allowed(&rules, &p) => Ok(p),
+                        Ok(p) => Err(de::Error::custom(format!("{} is not a {}", p, what))),
+                        Err(e) => Err(de::Error::custom(e)),
+                    })
+                    .collect::<Result<Vec<Pattern>, D::Error>>()
+            };
+            let knowledge = Knowledge {
+                rules,
+                codes: PatternSet::from_codes(rules, patterns(&written.codes, Rules::is_code, "code")?),
+                guessed: patterns(&written.guessed, Rules::is_guess, "guess")?,
+                responses: written.responses
+                    .iter()
+                    .map(|k| rules.parse_response(k).map_err(de::Error::custom))
+                    .collect::<Result<_, _>>()?,
+            };
+            if !knowledge.is_valid() {
+                return Err(de::Error::custom("a response is needed for each guess, but perhaps the last"));
             }
+            Ok(knowledge)
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{Pattern, Rules, shield, seeded_rng};
+    use super::{Hint, Solver, TieBreak};
+
+    #[test]
+    fn digit_sum() {
+        // Number Mastermind: the sum of the digits narrows S from the start.
+        let rules = Rules::new(3, 5);
+        let guesses = |hinted: bool| -> usize {
+            rules.codes()
+                .map(|code| {
+                    let breaker = Solver::with_rules(rules, shield(code));
+                    let breaker = if hinted { breaker.hint(Hint::DigitSum(code.digit_sum().unwrap())) } else { breaker };
+                    breaker.count()
+                })
+                .sum()
+        };
+        assert!(guesses(true) < guesses(false));
+    }
+
+    #[test]
+    fn tie_breaks() {
+        let code = Pattern::from_digits(['1', '1', '2', '3']);
+        let second_guess = |policy, seed| {
+            let mut breaker = Solver::new(shield(code)).tie_break(policy, Box::new(seeded_rng(seed)));
+            let first = breaker.play().expect("first guess");
+            breaker.retain_same_response(code.score(first));
+            let maximal = breaker.max_score_guesses();
+            (breaker.next_guess(), maximal, breaker.s)
+        };
+
+        let (knuth, maximal, s) = second_guess(TieBreak::LeastIndex, 0);
+        assert_eq!(knuth, Pattern::from_digits(['1', '2', '2', '3']));
+        assert!(maximal.len() > 1);
+
+        let randoms: Vec<Pattern> = (0..4).map(|seed| second_guess(TieBreak::RandomInS, seed).0).collect();
+        // No maximal guess is in S here, so RandomInS falls back to any.
+        assert!(!maximal.iter().any(|g| s.contains(g)));
+        assert!(randoms.iter().all(|g| maximal.contains(g)));
+        assert!(randoms.iter().any(|g| *g != knuth));
+        assert_eq!(second_guess(TieBreak::RandomInS, 3).0, randoms[3]);
+
+        assert!(maximal.contains(&second_guess(TieBreak::RandomMax, 0).0));
+        assert!(maximal.contains(&second_guess(TieBreak::MostParts, 0).0));
+    }
+
+    #[test]
+    fn bulls_and_cows() {
+        let rules = Rules::bulls_and_cows();
+        let code = rules.parse("9071").unwrap();
+        let guesses: Vec<Pattern> = Solver::with_rules(rules, shield(code)).collect();
+        assert_eq!(guesses.last(), Some(&code));
+        assert!(guesses.len() <= 7);
+        assert!(guesses.iter().all(|g| rules.is_guess(g)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        extern crate serde_json;
+        use gameplay::KeyPegs;
+        use super::PatternSet;
+
+        let mut s = Solver::possible_codes();
+        let guess = Solver::initial_guess();
+        s.filter_with(&|p: &Pattern| guess.score(*p) == KeyPegs::new().blacks(3));
+        assert_eq!(s.len(), 20);
+
+        let json = serde_json::to_string(&s).unwrap();
+        assert!(json.contains(r#""members":["1112","1121","1123","#));
+        assert_eq!(serde_json::from_str::<PatternSet>(&json).unwrap(), s);
+
+        // Read back under its own rules, not the default.
+        let rules = Rules::bulls_and_cows();
+        let mut s = PatternSet::codes(rules);
+        let guess = rules.parse("1234").unwrap();
+        s.filter_with(&|p: &Pattern| rules.score(*p, guess) == KeyPegs::new().blacks(3));
+        let json = serde_json::to_string(&s).unwrap();
+        assert_eq!(serde_json::from_str::<PatternSet>(&json).unwrap(), s);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn knowledge_round_trip() {
+        extern crate serde_json;
+        use gameplay::{Alphabet, shield_with};
+        use super::{Knowledge, PatternSet, Strategy};
+
+        // Patterns are read back under their own rules, not the default.
+        let rules = Rules::new(5, 8).with_alphabet(Alphabet::colors());
+        let code = rules.parse("WBRRC").unwrap();
+        let mut breaker = Solver::with_rules(rules, shield_with(rules, code)).strategy(Strategy::Consistent);
+        breaker.by_ref().take(3).count();
+        let knowledge = breaker.knowledge();
+
+        let json = serde_json::to_string(&knowledge).unwrap();
+        let read: Knowledge = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, knowledge);
+        let resumed = Solver::resume(read, shield_with(rules, code)).strategy(Strategy::Consistent);
+        assert_eq!(resumed.last(), Some(code));
+
+        let too_many = json.replace(r#""responses":["#, r#""responses":["B","B","#);
+        assert!(serde_json::from_str::<Knowledge>(&too_many).is_err());
+
+        // 1123 may be guessed, but can't be the code.
+        let rules = Rules::default().no_duplicates(false);
+        let guess = rules.parse("1123").unwrap();
+        let knowledge = Knowledge { rules, codes: PatternSet::empty(rules), guessed: vec![guess], responses: vec![] };
+        let json = serde_json::to_string(&knowledge).unwrap();
+        assert_eq!(serde_json::from_str::<Knowledge>(&json).unwrap(), knowledge);
+        let duplicates = json.replace(r#""codes":[]"#, r#""codes":["1123"]"#);
+        assert!(duplicates != json);
+        assert!(serde_json::from_str::<Knowledge>(&duplicates).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! Static Mastermind: the codebreaker submits every guess up front,
+//! receives all the responses at once, and must then name the code.
+//!
+//! A set of guesses will do if no two codes give the same responses
+//! to all of them, that is, if their signatures are distinct. For
+//! four pegs and six colors, the fewest that will do is six.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::{Pattern, Rules};
+//! use self::mastermind::static_mastermind::{ambiguities, identify, search};
+//!
+//! let rules = Rules::new(3, 3);
+//! let guesses = search(rules);
+//! assert!(ambiguities(rules, &guesses).is_empty());
+//!
+//! let code = rules.parse("132").unwrap();
+//! let responses: Vec<_> = guesses.iter().map(|&g| rules.score(code, g)).collect();
+//! assert_eq!(identify(rules, &guesses, &responses), Some(code));
+//!
+//! // These two guesses can't tell 122 from 212.
+//! let p = |s: &str| rules.parse(s).unwrap();
+//! let groups = ambiguities(rules, &[p("111"), p("333")]);
+//! assert!(groups.iter().any(|codes| codes.contains(&p("122")) && codes.contains(&p("212"))));
+//! ```
+
+use std::collections::HashMap;
+
+use gameplay::{KeyPegs, Pattern, Rules, MAX_PEGS};
+use solver::PatternSet;
+
+
+/// The responses of a code to each of the guesses.
+pub fn signature(rules: Rules, guesses: &[Pattern], code: Pattern) -> Vec<KeyPegs> {
+    guesses.iter().map(|&guess| rules.score(code, guess)).collect()
+}
+
+/// Groups of codes that the guesses can't tell apart; none, if the
+/// guesses identify every code.
+pub fn ambiguities(rules: Rules, guesses: &[Pattern]) -> Vec<PatternSet> {
+    let mut groups: HashMap<Vec<KeyPegs>, PatternSet> = HashMap::new();
+    for code in rules.codes() {
+        groups.entry(signature(rules, guesses, code)).or_insert_with(|| PatternSet::empty(rules)).insert(code);
+    }
+
+    let mut ambiguous: Vec<PatternSet> = groups.into_values().filter(|codes| codes.len() > 1).collect();
+    ambiguous.sort_by_key(|codes| codes.iter().next());
+    ambiguous
+}
+
+/// Name the code, given the responses to the guesses, if only one
+/// code would give them all.
+pub fn identify(rules: Rules, guesses: &[Pattern], responses: &[KeyPegs]) -> Option<Pattern> {
+    let mut matches = rules.codes().filter(|&code| signature(rules, guesses, code) == responses);
+    match (matches.next(), matches.next()) {
+        (Some(code), None) => Some(code),
+        _ => None,
+    }
+}
+
+
+/// Search for a small set of guesses that identifies every code.
+///
+/// Guesses are chosen greedily, each one splitting the groups of
+/// codes not yet told apart into as many groups as possible; among
+/// equals, the least guess. That need not be optimal, but for four
+/// pegs and six colors it finds six guesses, which is.
+pub fn search(rules: Rules) -> Vec<Pattern> {
+    let codes: Vec<Pattern> = rules.codes().collect();
+    let responses = (MAX_PEGS + 1) * (MAX_PEGS + 1);
+    let slot = |group: usize, k: KeyPegs| {
+        group * responses + k.black_count() as usize * (MAX_PEGS + 1) + k.white_count() as usize
+    };
+
+    // Codes told apart so far are in different groups; a slot is
+    // taken in the current round if it holds the round number.
+    let mut group = vec![0; codes.len()];
+    let mut groups = 1;
+    let mut taken = vec![0; codes.len() * responses];
+    let mut round = 0;
+    let mut guesses = vec![];
+
+    while groups < codes.len() {
+        let mut best: Option<(usize, Pattern)> = None;
+        for guess in rules.guesses() {
+            round += 1;
+            let mut count = 0;
+            for (ix, &code) in codes.iter().enumerate() {
+                let t = &mut taken[slot(group[ix], rules.score(code, guess))];
+                if *t != round {
+                    *t = round;
+                    count += 1;
+                }
+            }
+            best = match best {
+                Some((most, _)) if most >= count => best,
+                _ => Some((count, guess)),
+            };
+        }
+
+        let (count, guess) = best.expect("no guesses");
+        assert!(count > groups, "no guess tells the remaining codes apart");
+
+        let mut renumber: HashMap<usize, usize> = HashMap::new();
+        for (ix, &code) in codes.iter().enumerate() {
+            let next = renumber.len();
+            group[ix] = *renumber.entry(slot(group[ix], rules.score(code, guess))).or_insert(next);
+        }
+        groups = count;
+        guesses.push(guess);
+    }
+    guesses
+}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{Pattern, Rules};
+    use super::{ambiguities, search};
+
+    #[test]
+    fn verify() {
+        let rules = Rules::default();
+        let guesses: Vec<Pattern> = ["1123", "2453", "5662", "3264", "6535"]
+            .iter()
+            .map(|g| g.parse().unwrap())
+            .collect();
+        let groups = ambiguities(rules, &guesses);
+        assert_eq!(groups.iter().map(|codes| codes.len() - 1).sum::<usize>(), 1296 - 1264);
+
+        let more = [&guesses[..], &["3444".parse().unwrap()]].concat();
+        assert!(ambiguities(rules, &more).is_empty());
+    }
+
+    #[test]
+    #[ignore]
+    fn knuth() {
+        assert_eq!(search(Rules::default()).len(), 6);
+    }
+}
@@ -0,0 +1,214 @@
+//! Wordle: rather than counts of key pegs, each letter of a guess is
+//! marked exact (green), present (yellow) or absent (grey).
+//!
+//! Repeated letters are marked as Wordle does: exact matches first,
+//! then, from left to right, a letter is present only while the code
+//! has an occurrence of it not already accounted for; any more are
+//! absent.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::Alphabet;
+//! use self::mastermind::solver::solve;
+//! use self::mastermind::wordle::{Wordle, marks};
+//! use self::mastermind::words;
+//!
+//! let dictionary = words::read("brown\ncrane\ndrone\nknave\nnacre\nroute\nsnare\ntrace\n",
+//!                              Alphabet::letters()).unwrap();
+//! let word = |w: &str| dictionary.rules().parse(w).unwrap();
+//! assert_eq!(format!("{}", marks(word("crane"), word("nacre"))), "YYYYG");
+//!
+//! let code = word("knave");
+//! let guesses = solve(&Wordle::new(dictionary.rules()), dictionary.clone(), &dictionary, &|g| marks(code, *g));
+//! assert_eq!(guesses.last(), Some(&code));
+//! assert!(guesses.len() <= 3);
+//! ```
+
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use gameplay::{ParseError, Pattern, Rules, Scorer, MAX_COLORS, MAX_PEGS};
+
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
+pub enum Mark {
+    Absent,
+    Present,
+    Exact,
+}
+
+impl Mark {
+    fn symbol(&self) -> char {
+        match *self {
+            Mark::Absent => '.',
+            Mark::Present => 'Y',
+            Mark::Exact => 'G',
+        }
+    }
+}
+
+
+/// A mark for each position, written as by `Display`, e.g. ".YG.G".
+#[derive(PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord, Default)]
+pub struct Marks {
+    /// In base 3, first position first.
+    ix: u32,
+    pegs: u8,
+}
+
+impl Marks {
+    pub fn new(marks: &[Mark]) -> Marks {
+        assert!(marks.len() <= MAX_PEGS);
+        Marks {
+            ix: marks.iter().fold(0, |ix, &mark| ix * 3 + mark as u32),
+            pegs: marks.len() as u8,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pegs as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pegs == 0
+    }
+
+    pub fn get(&self, pos: usize) -> Mark {
+        assert!(pos < self.len());
+        match self.ix / 3u32.pow((self.len() - 1 - pos) as u32) % 3 {
+            0 => Mark::Absent,
+            1 => Mark::Present,
+            _ => Mark::Exact,
+        }
+    }
+
+    pub fn is_win(&self) -> bool {
+        (0..self.len()).all(|pos| self.get(pos) == Mark::Exact)
+    }
+}
+
+impl Display for Marks {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let s: String = (0..self.len()).map(|pos| self.get(pos).symbol()).collect();
+        fmt.write_str(&s)
+    }
+}
+
+impl Debug for Marks {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, fmt)
+    }
+}
+
+impl FromStr for Marks {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Marks, ParseError> {
+        let mut marks = vec![];
+        for c in s.chars() {
+            match c {
+                '.' => marks.push(Mark::Absent),
+                'Y' | 'y' => marks.push(Mark::Present),
+                'G' | 'g' => marks.push(Mark::Exact),
+                _ => return Err(ParseError::Symbol(c)),
+            }
+        }
+        if marks.len() > MAX_PEGS {
+            return Err(ParseError::Length(marks.len()));
+        }
+        Ok(Marks::new(&marks))
+    }
+}
+
+
+/// Mark each letter of the guess w.r.t. the code.
+pub fn marks(code: Pattern, guess: Pattern) -> Marks {
+    let (c, n) = code.decode();
+    let (g, _) = guess.decode();
+
+    let mut marks = [Mark::Absent; MAX_PEGS];
+    let mut unused = [0u8; MAX_COLORS + 1];
+    for pos in 0..n {
+        if c[pos] == g[pos] {
+            marks[pos] = Mark::Exact;
+        } else {
+            unused[c[pos] as usize] += 1;
+        }
+    }
+    for pos in 0..n {
+        let letter = g[pos] as usize;
+        if marks[pos] != Mark::Exact && unused[letter] > 0 {
+            unused[letter] -= 1;
+            marks[pos] = Mark::Present;
+        }
+    }
+    Marks::new(&marks[..n])
+}
+
+
+/// Wordle's marks, for patterns of the given rules.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Wordle {
+    pegs: u8,
+}
+
+impl Wordle {
+    pub fn new(rules: Rules) -> Wordle {
+        Wordle { pegs: rules.pegs }
+    }
+}
+
+impl Scorer for Wordle {
+    type Response = Marks;
+
+    fn score(&self, code: Pattern, guess: Pattern) -> Marks {
+        marks(code, guess)
+    }
+
+    fn is_win(&self, response: &Marks) -> bool {
+        response.is_win()
+    }
+
+    fn responses(&self) -> usize {
+        3usize.pow(self.pegs as u32)
+    }
+
+    fn response_index(&self, response: &Marks) -> usize {
+        response.ix as usize
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{Alphabet, Rules};
+    use solver::{PatternSet, entropy, max_entropy_guesses, minimax_guesses, partition};
+    use super::{Marks, Wordle, marks};
+
+    #[test]
+    fn repeated_letters() {
+        let rules = Rules::new(5, 26).with_alphabet(Alphabet::letters());
+        let word = |w: &str| rules.parse(w).unwrap();
+        let m = |s: &str| s.parse::<Marks>().unwrap();
+        assert_eq!(marks(word("abbey"), word("kebab")), m(".YGYY"));
+        assert_eq!(marks(word("crane"), word("eerie")), m("..Y.G"));
+        assert_eq!(marks(word("eerie"), word("crane")), m(".Y..G"));
+        assert!(marks(word("crane"), word("crane")).is_win());
+    }
+
+    #[test]
+    fn richer_than_key_pegs() {
+        let rules = Rules::default();
+        let s = PatternSet::codes(rules);
+        let guess = rules.initial_guess();
+        let wordle = Wordle::new(rules);
+        assert!(partition(&wordle, guess, &s).len() > partition(&rules, guess, &s).len());
+        assert!(entropy(&wordle, guess, &s) > entropy(&rules, guess, &s));
+
+        let worst = |g| partition(&wordle, g, &s).values().map(|part| part.len()).max();
+        let minimax = minimax_guesses(&wordle, rules.guesses(), &s)[0];
+        assert!(worst(minimax) < worst(guess));
+        let informative = max_entropy_guesses(&wordle, rules.guesses(), &s)[0];
+        assert!(entropy(&wordle, informative, &s) >= entropy(&wordle, minimax, &s));
+    }
+}
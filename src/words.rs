@@ -0,0 +1,143 @@
+//! Word Mastermind: codes are words from a dictionary, scored as
+//! usual, a letter in the right place being a black peg and a letter
+//! in the wrong place a white one.
+//!
+//! A dictionary file has one word per line, all of the same length;
+//! blank lines and lines starting with `#` are ignored. The codebreaker
+//! may be restricted to guessing words, or allowed any string of
+//! letters, which is thorough but slow.
+//!
+//! ```rust
+//! use self::mastermind::gameplay::{Alphabet, shield};
+//! use self::mastermind::solver::Solver;
+//! use self::mastermind::words;
+//!
+//! let dictionary = words::read("# some words\nbrown\ncrane\ndrone\nknave\nnacre\nroute\nsnare\ntrace\n",
+//!                              Alphabet::letters()).unwrap();
+//! assert_eq!(dictionary.len(), 8);
+//!
+//! let code = dictionary.rules().parse("route").unwrap();
+//! let breaker = Solver::with_codes(dictionary.clone(), shield(code)).guesses(dictionary);
+//! let guesses: Vec<String> = breaker.map(|g| g.to_string()).collect();
+//! assert_eq!(guesses.last().map(|g| g.as_str()), Some("route"));
+//! assert!(guesses.len() <= 3);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use gameplay::{Alphabet, ParseError, Pattern, Rules, MAX_PEGS};
+use solver::PatternSet;
+
+
+/// The words of a dictionary, under rules for words of their length
+/// over the alphabet, with letters repeated as words need. The set
+/// keeps just the words, not a bit for every string of letters.
+pub fn read(text: &str, alphabet: Alphabet) -> Result<PatternSet, DictionaryError> {
+    let mut rules: Option<Rules> = None;
+    let mut words: Vec<Pattern> = vec![];
+
+    for (ix, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let length = line.chars().count();
+        let word_rules = match rules {
+            Some(rules) => rules,
+            None => match Rules::checked(length.min(MAX_PEGS + 1) as u8, alphabet.len() as u8) {
+                Some(rules) => rules.with_alphabet(alphabet),
+                None => return Err(DictionaryError::TooLong(ix + 1, length, longest(alphabet))),
+            },
+        };
+        rules = Some(word_rules);
+        words.push(word_rules.parse(line).map_err(|e| DictionaryError::Word(ix + 1, e))?);
+    }
+
+    match rules {
+        Some(rules) => Ok(PatternSet::from_codes(rules, words)),
+        None => Err(DictionaryError::Empty),
+    }
+}
+
+/// The most letters a word may have: patterns are numbered in 32 bits,
+/// so there can be no more than 2^32 strings of letters, e.g. those of
+/// up to six letters out of 26.
+fn longest(alphabet: Alphabet) -> usize {
+    (1..MAX_PEGS + 1).take_while(|&n| Rules::checked(n as u8, alphabet.len() as u8).is_some()).last().unwrap_or(0)
+}
+
+pub fn load<P: AsRef<Path>>(path: P, alphabet: Alphabet) -> Result<PatternSet, DictionaryError> {
+    let mut text = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut text))
+        .map_err(|e| DictionaryError::Io(e.to_string()))?;
+    read(&text, alphabet)
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DictionaryError {
+    Io(String),
+    /// No words at all.
+    Empty,
+    /// A word, on the given line, of the wrong length or with a
+    /// symbol not in the alphabet.
+    Word(usize, ParseError),
+    /// The first word, on the given line, has more letters than the
+    /// most the alphabet allows, which follows.
+    TooLong(usize, usize, usize),
+}
+
+impl Display for DictionaryError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match *self {
+            DictionaryError::Io(ref msg) => fmt.write_str(msg),
+            DictionaryError::Empty => fmt.write_str("no words"),
+            DictionaryError::Word(line, ParseError::Length(n)) => write!(fmt, "line {}: word of {} letters", line, n),
+            DictionaryError::Word(line, ref e) => write!(fmt, "line {}: {}", line, e),
+            DictionaryError::TooLong(line, n, longest) => {
+                write!(fmt, "line {}: word of {} letters; at most {}", line, n, longest)?;
+                if longest < MAX_PEGS {
+                    fmt.write_str(", as patterns are numbered in 32 bits")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for DictionaryError {}
+
+
+#[cfg(test)]
+mod tests {
+    use gameplay::{Alphabet, ParseError};
+    use super::{DictionaryError, read};
+
+    #[test]
+    fn errors() {
+        assert_eq!(read("\n# nothing\n", Alphabet::letters()), Err(DictionaryError::Empty));
+        assert_eq!(read("cat\ndog\nhorse\n", Alphabet::letters()),
+                   Err(DictionaryError::Word(3, ParseError::Length(5))));
+        assert_eq!(read("cat\ndo9\n", Alphabet::letters()),
+                   Err(DictionaryError::Word(2, ParseError::Symbol('9'))));
+        assert_eq!(format!("{}", read("stranger\n", Alphabet::letters()).unwrap_err()),
+                   "line 1: word of 8 letters; at most 6, as patterns are numbered in 32 bits");
+        assert_eq!(read("cat\nstranger\n", Alphabet::letters()),
+                   Err(DictionaryError::Word(2, ParseError::Length(8))));
+    }
+
+    #[test]
+    fn six_letters() {
+        let dictionary = read("planet\nstrand\nzigzag\n", Alphabet::letters()).unwrap();
+        assert_eq!(dictionary.rules().cardinality(), 26 * 26 * 26 * 26 * 26 * 26);
+        let words: Vec<String> = dictionary.iter().map(|w| w.to_string()).collect();
+        assert_eq!(words, vec!["planet", "strand", "zigzag"]);
+        assert!(dictionary.contains(&dictionary.rules().parse("zigzag").unwrap()));
+    }
+}